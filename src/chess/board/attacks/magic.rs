@@ -0,0 +1,336 @@
+use std::sync::OnceLock;
+
+use crate::chess::board::bitboard::Bitboard;
+use crate::chess::board::square::{Direction, Square};
+
+const ROOK_DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+const BISHOP_DIRECTIONS: [Direction; 4] = [
+    Direction::UpLeft,
+    Direction::UpRight,
+    Direction::DownLeft,
+    Direction::DownRight,
+];
+
+// Found by a brute force search over sparse random numbers, such that every
+// relevant occupancy of a square maps to an index with the right attacks.
+#[rustfmt::skip]
+const ROOK_MAGICS: [u64; 64] = [
+    0x0980_0080_1140_0020,
+    0x8340_0044_1000_2000,
+    0x0880_2000_9000_8268,
+    0x0080_0800_8010_0004,
+    0x8100_1100_0402_0800,
+    0x0300_0100_0400_0822,
+    0x0880_1a00_2900_0080,
+    0x8100_0500_0120_4882,
+    0x0844_8000_8140_0320,
+    0x0804_4020_1000_4000,
+    0x0108_8020_0310_0480,
+    0x0004_8080_0800_1000,
+    0x0003_0018_0100_1014,
+    0x0002_0002_0004_1008,
+    0x0004_0081_0804_2210,
+    0x0105_0001_0000_9042,
+    0x0400_8080_0040_0021,
+    0xc100_4040_1000_2000,
+    0x0060_0080_1000_2088,
+    0x0400_8080_0800_1000,
+    0x4440_8080_0800_0400,
+    0x1002_0080_0400_0280,
+    0x4002_4400_300d_1248,
+    0x0010_0200_0040_8104,
+    0x0101_0082_0020_4200,
+    0x8020_0020_4000_5000,
+    0x4100_1000_8080_2000,
+    0x4008_006a_8010_0280,
+    0x1020_0800_8004_0080,
+    0x0004_0100_4002_0040,
+    0x0018_a124_0008_0290,
+    0x6140_0042_0000_8104,
+    0x4000_4000_2080_0090,
+    0x2020_0020_8080_4000,
+    0x0000_4082_0200_2010,
+    0x0080_1005_0100_0820,
+    0x0000_8004_0080_0800,
+    0x000a_2004_0801_4010,
+    0x0100_8002_0080_0100,
+    0xa008_0057_0200_008c,
+    0x0080_0040_6000_c010,
+    0x1040_1000_2800_2000,
+    0x0048_2001_0011_0040,
+    0x0068_4902_1003_0020,
+    0x1009_0800_0501_0010,
+    0x2142_0008_0401_0100,
+    0x1001_0801_1084_0002,
+    0x1801_0044_0082_0001,
+    0x0104_4020_8d02_0200,
+    0x0000_4000_2000_8080,
+    0x0200_2000_8010_0280,
+    0x0000_1000_2009_0100,
+    0x0204_0080_0802_0480,
+    0x8104_0100_4002_0040,
+    0x7800_0201_b008_0400,
+    0x0040_8000_5100_2880,
+    0x0050_1080_0100_2041,
+    0x208a_8011_0061_4003,
+    0x0006_0020_4208_9082,
+    0x0011_0900_0420_1001,
+    0x1002_0010_0420_0802,
+    0x0005_0002_0804_0001,
+    0x0002_0027_01ac_0822,
+    0x0000_1025_0184_004a,
+];
+
+#[rustfmt::skip]
+const BISHOP_MAGICS: [u64; 64] = [
+    0xc0a0_0122_0604_0ea0,
+    0x8010_2282_0042_0001,
+    0x0110_0082_2040_0400,
+    0x0244_5c00_8010_6000,
+    0x0044_0420_0400_8100,
+    0x0880_9004_2040_8c05,
+    0x0201_0801_1008_0002,
+    0x0000_1080_9420_2000,
+    0x0000_0420_0204_0108,
+    0x0000_6230_2411_0042,
+    0x0086_1000_9481_1002,
+    0x0000_0445_0200_2080,
+    0x0100_4602_1140_0040,
+    0x0008_1090_0420_0004,
+    0x0202_3200_8484_4000,
+    0x8040_0424_2104_1009,
+    0x2010_10c0_5102_008c,
+    0x1020_8882_0802_4080,
+    0x0108_000c_8029_0200,
+    0x8048_0004_2042_5203,
+    0x0005_0000_9040_2000,
+    0x2080_4002_0110_4100,
+    0x8820_4201_1110_1000,
+    0x4ac0_3022_0882_1802,
+    0x0004_4000_1002_a840,
+    0x2002_2000_1004_1080,
+    0x1012_0802_0100_4400,
+    0x8440_0400_0241_0120,
+    0x1090_8200_8401_0400,
+    0x2084_8520_1202_1000,
+    0x1204_0062_c101_1003,
+    0x0200_8205_e109_0080,
+    0x088c_1028_0804_2080,
+    0x0802_1022_0090_4280,
+    0x8020_2090_0208_0020,
+    0x2200_0808_0006_0a00,
+    0x20c0_0040_1001_0100,
+    0x0802_0041_0082_1003,
+    0x0008_0244_0000_8080,
+    0x0000_8401_0200_8090,
+    0x0030_a404_2024_4007,
+    0x0a19_0842_1001_1282,
+    0x0004_0820_9001_9806,
+    0x6108_0042_0802_0080,
+    0x0081_2004_1011_0100,
+    0x1040_8107_0101_0208,
+    0x0282_0478_3201_2080,
+    0x0010_0200_9900_0020,
+    0x000e_0104_2240_0840,
+    0x1020_4208_b008_9090,
+    0x0810_0444_0c04_8000,
+    0x88c0_1800_8404_0001,
+    0x3100_0208_0304_0080,
+    0x8900_70a0_4121_0c00,
+    0x0020_2001_0101_0a09,
+    0x0004_1002_4041_0400,
+    0x0006_0044_0208_0200,
+    0x0801_0624_8404_2000,
+    0x0001_0002_d744_1004,
+    0x0810_0800_0020_8800,
+    0x0000_0208_0803_0411,
+    0x1450_0010_2001_4440,
+    0x0040_6008_1081_a288,
+    0x0044_0114_0410_8a00,
+];
+
+/// Maps the occupancy of the squares a slider on a square could be blocked on
+/// to an index into the attack table.
+struct Magic {
+    mask: Bitboard,
+    #[cfg_attr(all(target_arch = "x86_64", target_feature = "bmi2"), allow(dead_code))]
+    magic: u64,
+    #[cfg_attr(all(target_arch = "x86_64", target_feature = "bmi2"), allow(dead_code))]
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    #[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+    fn index(&self, occupied: Bitboard) -> usize {
+        use std::arch::x86_64::_pext_u64;
+
+        // PEXT packs the relevant occupancy bits densely, no magic needed
+        // SAFETY: this is only compiled if the target supports BMI2
+        self.offset + unsafe { _pext_u64(occupied.0, self.mask.0) } as usize
+    }
+
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+    fn index(&self, occupied: Bitboard) -> usize {
+        let relevant = occupied.0 & self.mask.0;
+        self.offset + (relevant.wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct Tables {
+    bishop: [Magic; 64],
+    rook: [Magic; 64],
+    attacks: Vec<Bitboard>,
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+fn tables() -> &'static Tables {
+    TABLES.get_or_init(|| {
+        // 5248 bishop and 102400 rook entries
+        let mut attacks = Vec::with_capacity(107_648);
+        let bishop = init_magics(&mut attacks, &BISHOP_MAGICS, &BISHOP_DIRECTIONS);
+        let rook = init_magics(&mut attacks, &ROOK_MAGICS, &ROOK_DIRECTIONS);
+        Tables {
+            bishop,
+            rook,
+            attacks,
+        }
+    })
+}
+
+pub(super) fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.attacks[tables.bishop[square as usize].index(occupied)]
+}
+
+pub(super) fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.attacks[tables.rook[square as usize].index(occupied)]
+}
+
+fn init_magics(
+    attacks: &mut Vec<Bitboard>,
+    magics: &[u64; 64],
+    directions: &[Direction; 4],
+) -> [Magic; 64] {
+    Square::ALL.map(|square| {
+        let mask = relevant_occupancy(square, directions);
+        let magic = Magic {
+            mask,
+            magic: magics[square as usize],
+            shift: 64 - mask.count(),
+            offset: attacks.len(),
+        };
+        attacks.resize(attacks.len() + (1 << mask.count()), Bitboard::EMPTY);
+
+        // enumerate all subsets of the mask
+        let mut occupied = Bitboard::EMPTY;
+        loop {
+            let index = magic.index(occupied);
+            let expected = ray_attacks(square, occupied, directions);
+            debug_assert!(
+                attacks[index].is_empty() || attacks[index] == expected,
+                "bad magic for {}",
+                square
+            );
+            attacks[index] = expected;
+
+            occupied = Bitboard(occupied.0.wrapping_sub(mask.0) & mask.0);
+            if occupied.is_empty() {
+                break;
+            }
+        }
+        magic
+    })
+}
+
+/// The squares that can block a slider. The last square of every ray is left
+/// out, since it is attacked no matter whether it is occupied.
+fn relevant_occupancy(square: Square, directions: &[Direction]) -> Bitboard {
+    let mut mask = Bitboard::EMPTY;
+    for &dir in directions {
+        let mut bb = Bitboard::from(square).shift(dir);
+        while !bb.shift(dir).is_empty() {
+            mask |= bb;
+            bb = bb.shift(dir);
+        }
+    }
+    mask
+}
+
+/// Walks the rays step by step, up to and including the first occupied square.
+fn ray_attacks(square: Square, occupied: Bitboard, directions: &[Direction]) -> Bitboard {
+    let mut attacks = Bitboard::EMPTY;
+    for &dir in directions {
+        let mut bb = Bitboard::from(square).shift(dir);
+        while !bb.is_empty() {
+            attacks |= bb;
+            if !(bb & occupied).is_empty() {
+                break;
+            }
+            bb = bb.shift(dir);
+        }
+    }
+    attacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::square::Square::*;
+
+    #[test]
+    fn test_matches_ray_attacks() {
+        // xorshift, to get reproducible occupancies of different densities
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut random = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for square in Square::ALL {
+            for i in 0..200 {
+                let occupied = Bitboard(match i % 3 {
+                    0 => random(),
+                    1 => random() & random(),
+                    _ => random() & random() & random(),
+                });
+                assert_eq!(
+                    ray_attacks(square, occupied, &BISHOP_DIRECTIONS),
+                    bishop_attacks(square, occupied),
+                    "bishop on {} with {:?}",
+                    square,
+                    occupied
+                );
+                assert_eq!(
+                    ray_attacks(square, occupied, &ROOK_DIRECTIONS),
+                    rook_attacks(square, occupied),
+                    "rook on {} with {:?}",
+                    square,
+                    occupied
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_relevant_occupancy() {
+        assert_eq!(
+            Bitboard::from_iter([A2, A3, A4, A5, A6, A7, B1, C1, D1, E1, F1, G1]),
+            relevant_occupancy(A1, &ROOK_DIRECTIONS)
+        );
+        assert_eq!(
+            Bitboard::from_iter([D5, C6, B7, F5, G6, F3, G2, D3, C2]),
+            relevant_occupancy(E4, &BISHOP_DIRECTIONS)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Attack sets of the pieces, looked up from precomputed tables.
+
+use crate::chess::board::bitboard::Bitboard;
+use crate::chess::board::piece::Color;
+use crate::chess::board::square::{Direction, Square};
+
+mod magic;
+
+static KNIGHT_ATTACKS: [Bitboard; 64] = leaper_table(&[
+    Direction::UpUpLeft,
+    Direction::UpUpRight,
+    Direction::LeftLeftUp,
+    Direction::LeftLeftDown,
+    Direction::RightRightUp,
+    Direction::RightRightDown,
+    Direction::DownDownLeft,
+    Direction::DownDownRight,
+]);
+
+static KING_ATTACKS: [Bitboard; 64] = leaper_table(&[
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+    Direction::UpLeft,
+    Direction::UpRight,
+    Direction::DownLeft,
+    Direction::DownRight,
+]);
+
+/// Indexed by color first, black pawns attack downwards and white pawns upwards.
+static PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_table(&[Direction::DownLeft, Direction::DownRight]),
+    leaper_table(&[Direction::UpLeft, Direction::UpRight]),
+];
+
+/// The squares strictly between two squares that share a rank, file or
+/// diagonal, indexed by both squares. Squares that don't share a line, or are
+/// next to each other, have nothing in between.
+static BETWEEN: [[Bitboard; 64]; 64] = between_table();
+
+/// The squares a piece that jumps one step into any of the directions
+/// attacks, for every square it could stand on.
+const fn leaper_table(directions: &[Direction]) -> [Bitboard; 64] {
+    let mut table = [Bitboard::EMPTY; 64];
+    let mut square = 0;
+    while square < 64 {
+        let bb = Bitboard(1 << square);
+        let mut i = 0;
+        while i < directions.len() {
+            table[square].0 |= bb.shift(directions[i]).0;
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const fn between_table() -> [[Bitboard; 64]; 64] {
+    let directions = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+    let mut table = [[Bitboard::EMPTY; 64]; 64];
+    let mut from = 0;
+    while from < 64 {
+        let mut i = 0;
+        while i < directions.len() {
+            // walk to the edge, remembering the squares passed on the way
+            let mut passed = Bitboard::EMPTY;
+            let mut bb = Bitboard(1 << from).shift(directions[i]);
+            while !bb.is_empty() {
+                table[from][bb.0.trailing_zeros() as usize] = passed;
+                passed.0 |= bb.0;
+                bb = bb.shift(directions[i]);
+            }
+            i += 1;
+        }
+        from += 1;
+    }
+    table
+}
+
+/// The squares a knight on `square` attacks.
+pub fn knight(square: Square) -> Bitboard {
+    KNIGHT_ATTACKS[square as usize]
+}
+
+/// The squares a king on `square` attacks.
+pub fn king(square: Square) -> Bitboard {
+    KING_ATTACKS[square as usize]
+}
+
+/// The squares a pawn of the given color on `square` attacks, i.e. could
+/// capture on. This doesn't include en passant.
+pub fn pawn(color: Color, square: Square) -> Bitboard {
+    PAWN_ATTACKS[color as usize][square as usize]
+}
+
+/// The squares a bishop on `square` attacks, up to and including the first
+/// occupied square in every direction.
+pub fn bishop(square: Square, occupied: Bitboard) -> Bitboard {
+    magic::bishop_attacks(square, occupied)
+}
+
+/// The squares a rook on `square` attacks, up to and including the first
+/// occupied square in every direction.
+pub fn rook(square: Square, occupied: Bitboard) -> Bitboard {
+    magic::rook_attacks(square, occupied)
+}
+
+/// The squares a queen on `square` attacks, up to and including the first
+/// occupied square in every direction.
+pub fn queen(square: Square, occupied: Bitboard) -> Bitboard {
+    bishop(square, occupied) | rook(square, occupied)
+}
+
+/// The squares strictly between `a` and `b` if they share a rank, file or
+/// diagonal, or no squares otherwise.
+pub fn between(a: Square, b: Square) -> Bitboard {
+    BETWEEN[a as usize][b as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::square::Square::*;
+
+    #[test]
+    fn test_knight() {
+        assert_eq!(Bitboard::from_iter([B3, C2]), knight(A1));
+        assert_eq!(
+            Bitboard::from_iter([D6, F6, C5, G5, C3, G3, D2, F2]),
+            knight(E4)
+        );
+        assert_eq!(Bitboard::from_iter([F7, G6]), knight(H8));
+    }
+
+    #[test]
+    fn test_king() {
+        assert_eq!(Bitboard::from_iter([A2, B2, B1]), king(A1));
+        assert_eq!(
+            Bitboard::from_iter([D5, E5, F5, D4, F4, D3, E3, F3]),
+            king(E4)
+        );
+        assert_eq!(8, king(B7).count());
+    }
+
+    #[test]
+    fn test_pawn() {
+        assert_eq!(Bitboard::from_iter([D5, F5]), pawn(Color::White, E4));
+        assert_eq!(Bitboard::from_iter([D3, F3]), pawn(Color::Black, E4));
+        assert_eq!(Bitboard::from(B3), pawn(Color::White, A2));
+        assert_eq!(Bitboard::from(G6), pawn(Color::Black, H7));
+        assert_eq!(Bitboard::EMPTY, pawn(Color::White, C8));
+    }
+
+    #[test]
+    fn test_between() {
+        assert_eq!(Bitboard::from_iter([B1, C1, D1]), between(A1, E1));
+        assert_eq!(Bitboard::from_iter([E2, E3]), between(E4, E1));
+        assert_eq!(
+            Bitboard::from_iter([B2, C3, D4, E5, F6, G7]),
+            between(H8, A1)
+        );
+        assert_eq!(Bitboard::from_iter([C5, B6]), between(D4, A7));
+        assert_eq!(Bitboard::EMPTY, between(E4, E5));
+        assert_eq!(Bitboard::EMPTY, between(E4, F6));
+        assert_eq!(Bitboard::EMPTY, between(A1, A1));
+    }
+}
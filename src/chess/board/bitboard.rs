@@ -0,0 +1,293 @@
+use std::fmt::{Debug, Formatter};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+use crate::chess::board::square::{Direction, Square};
+
+/// A set of squares, one bit per square, with bit 0 being A1 and bit 63 being H8.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const FULL: Bitboard = Bitboard(!0);
+
+    pub const FILE_A: Bitboard = Bitboard(0x0101_0101_0101_0101);
+    pub const FILE_B: Bitboard = Bitboard(Self::FILE_A.0 << 1);
+    pub const FILE_G: Bitboard = Bitboard(Self::FILE_A.0 << 6);
+    pub const FILE_H: Bitboard = Bitboard(Self::FILE_A.0 << 7);
+
+    pub const RANK_1: Bitboard = Bitboard(0xff);
+    pub const RANK_2: Bitboard = Bitboard(Self::RANK_1.0 << 8);
+    pub const RANK_3: Bitboard = Bitboard(Self::RANK_1.0 << 16);
+    pub const RANK_4: Bitboard = Bitboard(Self::RANK_1.0 << 24);
+    pub const RANK_5: Bitboard = Bitboard(Self::RANK_1.0 << 32);
+    pub const RANK_6: Bitboard = Bitboard(Self::RANK_1.0 << 40);
+    pub const RANK_7: Bitboard = Bitboard(Self::RANK_1.0 << 48);
+    pub const RANK_8: Bitboard = Bitboard(Self::RANK_1.0 << 56);
+
+    /// The file with the given number, 1 being the A file.
+    pub const fn file(file: u8) -> Bitboard {
+        Bitboard(Self::FILE_A.0 << (file - 1))
+    }
+
+    /// The rank with the given number, starting at 1.
+    pub const fn rank(rank: u8) -> Bitboard {
+        Bitboard(Self::RANK_1.0 << (8 * (rank - 1)))
+    }
+
+    pub const fn from_square(square: Square) -> Bitboard {
+        Bitboard(1 << square as u8)
+    }
+
+    pub const fn contains(&self, square: Square) -> bool {
+        self.0 & (1 << square as u8) != 0
+    }
+
+    pub fn set(&mut self, square: Square) {
+        self.0 |= 1 << square as u8;
+    }
+
+    pub fn clear(&mut self, square: Square) {
+        self.0 &= !(1 << square as u8);
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn count(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// The square with the lowest index in this set, if any.
+    pub fn first(&self) -> Option<Square> {
+        (!self.is_empty()).then(|| Square::from(self.0.trailing_zeros() as usize))
+    }
+
+    /// Moves every square of the set one step into the given direction.
+    /// Squares that would leave the board are dropped, they don't wrap around.
+    pub const fn shift(self, direction: Direction) -> Bitboard {
+        // squares on these files would wrap around to the other side of the board
+        let wrapping = match direction {
+            Direction::Left | Direction::UpLeft | Direction::DownLeft => Self::FILE_A.0,
+            Direction::Right | Direction::UpRight | Direction::DownRight => Self::FILE_H.0,
+            Direction::UpUpLeft | Direction::DownDownLeft => Self::FILE_A.0,
+            Direction::UpUpRight | Direction::DownDownRight => Self::FILE_H.0,
+            Direction::LeftLeftUp | Direction::LeftLeftDown => Self::FILE_A.0 | Self::FILE_B.0,
+            Direction::RightRightUp | Direction::RightRightDown => Self::FILE_G.0 | Self::FILE_H.0,
+            Direction::Up | Direction::Down => 0,
+        };
+        let bits = self.0 & !wrapping;
+        let offset = direction as i8;
+        if offset > 0 {
+            Bitboard(bits << offset)
+        } else {
+            Bitboard(bits >> -offset)
+        }
+    }
+}
+
+impl From<Square> for Bitboard {
+    fn from(square: Square) -> Self {
+        Bitboard::from_square(square)
+    }
+}
+
+impl FromIterator<Square> for Bitboard {
+    fn from_iter<T: IntoIterator<Item = Square>>(iter: T) -> Self {
+        let mut bb = Bitboard::EMPTY;
+        for square in iter {
+            bb.set(square);
+        }
+        bb
+    }
+}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = Squares;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Squares(self.0)
+    }
+}
+
+/// Iterates over the squares of a [`Bitboard`] from A1 to H8.
+pub struct Squares(u64);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = Square::from(self.0.trailing_zeros() as usize);
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Squares {}
+
+macro_rules! bitboard_op_impl {
+    ($trait:ident, $fn:ident, $assign_trait:ident, $assign_fn:ident, $op:tt) => {
+        impl $trait for Bitboard {
+            type Output = Bitboard;
+
+            fn $fn(self, rhs: Self) -> Self::Output {
+                Bitboard(self.0 $op rhs.0)
+            }
+        }
+
+        impl $assign_trait for Bitboard {
+            fn $assign_fn(&mut self, rhs: Self) {
+                self.0 = self.0 $op rhs.0;
+            }
+        }
+    };
+}
+
+bitboard_op_impl!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+bitboard_op_impl!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+bitboard_op_impl!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Self::Output {
+        Bitboard(!self.0)
+    }
+}
+
+impl Debug for Bitboard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Bitboard({:#018x})", self.0)?;
+        for rank in (1..=8).rev() {
+            for file in 1..=8 {
+                let square = Square::from_coordinates(rank, file);
+                write!(f, "{}", if self.contains(square) { 'x' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::square::Square::*;
+
+    #[test]
+    fn test_set_operations() {
+        let a = Bitboard::from_iter([A1, C3, H8]);
+        let b = Bitboard::from_iter([C3, D4]);
+
+        assert_eq!(Bitboard::from(C3), a & b);
+        assert_eq!(Bitboard::from_iter([A1, C3, D4, H8]), a | b);
+        assert_eq!(Bitboard::from_iter([A1, D4, H8]), a ^ b);
+        assert_eq!(61, (!a).count());
+        assert!(a.contains(H8));
+        assert!(!a.contains(D4));
+        assert_eq!(Some(A1), a.first());
+        assert_eq!(None, Bitboard::EMPTY.first());
+    }
+
+    #[test]
+    fn test_set_clear() {
+        let mut bb = Bitboard::EMPTY;
+        bb.set(E4);
+        bb.set(E5);
+        bb.clear(E4);
+
+        assert_eq!(Bitboard::from(E5), bb);
+    }
+
+    #[test]
+    fn test_iteration() {
+        let squares: Vec<Square> = Bitboard::from_iter([H8, A1, E4]).into_iter().collect();
+        assert_eq!(vec![A1, E4, H8], squares);
+
+        assert_eq!(64, Bitboard::FULL.into_iter().count());
+        assert_eq!(0, Bitboard::EMPTY.into_iter().count());
+    }
+
+    #[test]
+    fn test_files_and_ranks() {
+        assert_eq!(Bitboard::FILE_A, Bitboard::file(1));
+        assert_eq!(Bitboard::FILE_H, Bitboard::file(8));
+        assert_eq!(Bitboard::RANK_1, Bitboard::rank(1));
+        assert_eq!(Bitboard::RANK_8, Bitboard::rank(8));
+
+        for square in Square::ALL {
+            assert!(Bitboard::file(square.file()).contains(square));
+            assert!(Bitboard::rank(square.rank()).contains(square));
+        }
+    }
+
+    #[test]
+    fn test_shift() {
+        let e4 = Bitboard::from(E4);
+        assert_eq!(Bitboard::from(E5), e4.shift(Direction::Up));
+        assert_eq!(Bitboard::from(E3), e4.shift(Direction::Down));
+        assert_eq!(Bitboard::from(D4), e4.shift(Direction::Left));
+        assert_eq!(Bitboard::from(F4), e4.shift(Direction::Right));
+        assert_eq!(Bitboard::from(F6), e4.shift(Direction::UpUpRight));
+        assert_eq!(Bitboard::from(C3), e4.shift(Direction::LeftLeftDown));
+    }
+
+    #[test]
+    fn test_shift_does_not_wrap() {
+        assert_eq!(Bitboard::EMPTY, Bitboard::FILE_A.shift(Direction::Left));
+        assert_eq!(Bitboard::EMPTY, Bitboard::FILE_H.shift(Direction::Right));
+        assert_eq!(Bitboard::EMPTY, Bitboard::RANK_8.shift(Direction::Up));
+        assert_eq!(Bitboard::EMPTY, Bitboard::RANK_1.shift(Direction::Down));
+        assert_eq!(
+            Bitboard::EMPTY,
+            Bitboard::from(B4).shift(Direction::LeftLeftUp)
+        );
+        assert_eq!(
+            Bitboard::EMPTY,
+            Bitboard::from(G4).shift(Direction::RightRightUp)
+        );
+        assert_eq!(
+            Bitboard::EMPTY,
+            Bitboard::from(H7).shift(Direction::UpUpRight)
+        );
+
+        // every direction moves a center square, but no square of the border leaves the board
+        // towards the other side
+        for square in Square::ALL {
+            let bb = Bitboard::from(square);
+            for dir in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+                Direction::UpLeft,
+                Direction::UpRight,
+                Direction::DownLeft,
+                Direction::DownRight,
+                Direction::UpUpLeft,
+                Direction::UpUpRight,
+                Direction::LeftLeftUp,
+                Direction::LeftLeftDown,
+                Direction::RightRightUp,
+                Direction::RightRightDown,
+                Direction::DownDownLeft,
+                Direction::DownDownRight,
+            ] {
+                if let Some(target) = bb.shift(dir).first() {
+                    assert_eq!(square + dir, target);
+                    assert!((square.file() as i8 - target.file() as i8).abs() <= 2);
+                }
+            }
+        }
+    }
+}
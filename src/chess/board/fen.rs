@@ -0,0 +1,427 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::chess::board::bitboard::Bitboard;
+use crate::chess::board::piece::{Color, Kind, Piece};
+use crate::chess::board::r#move::{Flags, Move};
+use crate::chess::board::square::{Direction, Square};
+use crate::chess::board::Board;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FenError {
+    /// A FEN needs the four position fields, optionally followed by the two move counters.
+    WrongFieldCount(usize),
+    /// The piece placement does not consist of exactly eight ranks.
+    WrongRankCount(usize),
+    /// A rank in the piece placement does not describe exactly eight squares.
+    WrongRankLength {
+        rank: u8,
+    },
+    InvalidPiece(char),
+    /// Each side needs exactly one king.
+    WrongKingCount {
+        color: Color,
+        count: u32,
+    },
+    /// Pawns can't stand on the first or the eighth rank.
+    PawnOnBackRank(Square),
+    InvalidSideToMove(String),
+    InvalidCastlingRights(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::WrongFieldCount(n) => write!(f, "expected 4 or 6 fields, got {}", n),
+            FenError::WrongRankCount(n) => write!(f, "expected 8 ranks, got {}", n),
+            FenError::WrongRankLength { rank } => {
+                write!(f, "rank {} does not describe exactly 8 squares", rank)
+            }
+            FenError::InvalidPiece(c) => write!(f, "invalid piece '{}'", c),
+            FenError::WrongKingCount { color, count } => {
+                let color = match color {
+                    Color::White => "white",
+                    Color::Black => "black",
+                };
+                write!(f, "expected one {} king, got {}", color, count)
+            }
+            FenError::PawnOnBackRank(square) => {
+                write!(f, "pawn on the back rank at {}", square.to_algebraic())
+            }
+            FenError::InvalidSideToMove(s) => write!(f, "invalid side to move '{}'", s),
+            FenError::InvalidCastlingRights(s) => write!(f, "invalid castling rights '{}'", s),
+            FenError::InvalidEnPassant(s) => write!(f, "invalid en passant square '{}'", s),
+            FenError::InvalidHalfmoveClock(s) => write!(f, "invalid halfmove clock '{}'", s),
+            FenError::InvalidFullmoveNumber(s) => write!(f, "invalid fullmove number '{}'", s),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+impl Board {
+    /// Creates a board from a position in Forsyth-Edwards Notation.
+    ///
+    /// The halfmove clock and fullmove number may be omitted, in which case they
+    /// default to `0` and `1`.
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+
+        let mut b = Board::new();
+        b.parse_placement(fields[0])?;
+
+        b.side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            s => return Err(FenError::InvalidSideToMove(s.to_string())),
+        };
+
+        b.parse_castling_rights(fields[2])?;
+        b.parse_en_passant(fields[3])?;
+
+        if fields.len() == 6 {
+            b.halfmove_clock = fields[4]
+                .parse()
+                .map_err(|_| FenError::InvalidHalfmoveClock(fields[4].to_string()))?;
+            b.fullmove_number = match fields[5].parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(FenError::InvalidFullmoveNumber(fields[5].to_string())),
+            };
+        }
+
+        b.zobrist_key = b.compute_zobrist_key();
+        Ok(b)
+    }
+
+    fn parse_placement(&mut self, placement: &str) -> Result<(), FenError> {
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::WrongRankCount(ranks.len()));
+        }
+
+        // FEN lists the ranks from 8 down to 1
+        for (rank, row) in (1..=8_u8).rev().zip(ranks) {
+            let mut file = 1_u8;
+            for c in row.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    if !(1..=8).contains(&empty) {
+                        return Err(FenError::InvalidPiece(c));
+                    }
+                    file += empty as u8;
+                } else {
+                    let piece = Piece::from_char(c).ok_or(FenError::InvalidPiece(c))?;
+                    if file > 8 {
+                        return Err(FenError::WrongRankLength { rank });
+                    }
+                    self.place(Square::from_coordinates(rank, file), piece);
+                    file += 1;
+                }
+
+                if file > 9 {
+                    return Err(FenError::WrongRankLength { rank });
+                }
+            }
+            if file != 9 {
+                return Err(FenError::WrongRankLength { rank });
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            let count = self.bitboard(color, Kind::King).count();
+            if count != 1 {
+                return Err(FenError::WrongKingCount { color, count });
+            }
+        }
+        let back_ranks = Bitboard::RANK_1 | Bitboard::RANK_8;
+        if let Some(square) = (self.kind_bitboard(Kind::Pawn) & back_ranks).first() {
+            return Err(FenError::PawnOnBackRank(square));
+        }
+
+        Ok(())
+    }
+
+    fn parse_castling_rights(&mut self, rights: &str) -> Result<(), FenError> {
+        self.castle_rights_white_kingside = false;
+        self.castle_rights_white_queenside = false;
+        self.castle_rights_black_kingside = false;
+        self.castle_rights_black_queenside = false;
+
+        if rights == "-" {
+            return Ok(());
+        }
+
+        let invalid = || FenError::InvalidCastlingRights(rights.to_string());
+        if rights.is_empty() {
+            return Err(invalid());
+        }
+        for c in rights.chars() {
+            let right = match c {
+                'K' => &mut self.castle_rights_white_kingside,
+                'Q' => &mut self.castle_rights_white_queenside,
+                'k' => &mut self.castle_rights_black_kingside,
+                'q' => &mut self.castle_rights_black_queenside,
+                _ => return Err(invalid()),
+            };
+            if *right {
+                // every right may only be listed once
+                return Err(invalid());
+            }
+            *right = true;
+        }
+
+        Ok(())
+    }
+
+    fn parse_en_passant(&mut self, ep: &str) -> Result<(), FenError> {
+        if ep == "-" {
+            return Ok(());
+        }
+
+        let target =
+            Square::from_algebraic(ep).ok_or_else(|| FenError::InvalidEnPassant(ep.to_string()))?;
+        // the board only knows about en passant through the last move, so we
+        // reconstruct the pawn sprint that led to the target square
+        let (target_rank, from_dir, to_dir) = match self.side_to_move {
+            Color::White => (6, Direction::Up, Direction::Down),
+            Color::Black => (3, Direction::Down, Direction::Up),
+        };
+        if target.rank() != target_rank {
+            return Err(FenError::InvalidEnPassant(ep.to_string()));
+        }
+        let from = target + from_dir;
+        let to = target + to_dir;
+        if self[to] != Some(Piece::new(self.side_to_move.other(), Kind::Pawn)) {
+            return Err(FenError::InvalidEnPassant(ep.to_string()));
+        }
+
+        self.last_move = Some(Move::new(from, to, Flags::PAWN_SPRINT));
+        Ok(())
+    }
+
+    /// Formats the position in Forsyth-Edwards Notation.
+    ///
+    /// The en passant square is written whenever the last move was a pawn sprint,
+    /// regardless of whether an en passant capture is actually possible.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+
+        for rank in (1..=8_u8).rev() {
+            let mut empty = 0;
+            for file in 1..=8_u8 {
+                match self[Square::from_coordinates(rank, file)] {
+                    None => empty += 1,
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece.to_char());
+                    }
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 1 {
+                fen.push('/');
+            }
+        }
+
+        fen.push(' ');
+        fen.push(match self.side_to_move {
+            Color::White => 'w',
+            Color::Black => 'b',
+        });
+
+        fen.push(' ');
+        let rights_len = fen.len();
+        for (has_right, c) in [
+            (self.castle_rights_white_kingside, 'K'),
+            (self.castle_rights_white_queenside, 'Q'),
+            (self.castle_rights_black_kingside, 'k'),
+            (self.castle_rights_black_queenside, 'q'),
+        ] {
+            if has_right {
+                fen.push(c);
+            }
+        }
+        if fen.len() == rights_len {
+            fen.push('-');
+        }
+
+        fen.push(' ');
+        match &self.last_move {
+            Some(mov) if mov.is_pawn_sprint() => {
+                let target = Square::from((mov.from() as usize + mov.to() as usize) / 2);
+                fen.push_str(&target.to_algebraic());
+            }
+            _ => fen.push('-'),
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+
+        fen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::setup::{default_setup, DEFAULT_FEN};
+    use crate::chess::board::square::Square::*;
+
+    #[test]
+    fn test_default_setup_from_fen() {
+        let mut expected = Board::new();
+        expected.populate(default_setup);
+
+        assert_eq!(Ok(expected), Board::from_fen(DEFAULT_FEN));
+    }
+
+    #[test]
+    fn test_default_setup_to_fen() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+
+        assert_eq!(DEFAULT_FEN, b.to_fen());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for fen in [
+            DEFAULT_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "4k3/8/8/8/8/8/8/4K3 b - - 42 100",
+        ] {
+            let b = Board::from_fen(fen).unwrap();
+            assert_eq!(fen, b.to_fen());
+        }
+    }
+
+    #[test]
+    fn test_fields() {
+        let b = Board::from_fen("r3k3/8/8/8/4Pp2/8/8/4K2R b Kq e3 3 27").unwrap();
+
+        assert_eq!(Some(Piece::new(Color::Black, Kind::Rook)), b[A8]);
+        assert_eq!(Some(Piece::new(Color::Black, Kind::King)), b[E8]);
+        assert_eq!(Some(Piece::new(Color::White, Kind::Pawn)), b[E4]);
+        assert_eq!(Some(Piece::new(Color::Black, Kind::Pawn)), b[F4]);
+        assert_eq!(Some(Piece::new(Color::White, Kind::King)), b[E1]);
+        assert_eq!(Some(Piece::new(Color::White, Kind::Rook)), b[H1]);
+        assert_eq!(6, b.pieces().len());
+
+        assert_eq!(Color::Black, b.side_to_move());
+        assert!(b.castle_rights_white_kingside);
+        assert!(!b.castle_rights_white_queenside);
+        assert!(!b.castle_rights_black_kingside);
+        assert!(b.castle_rights_black_queenside);
+        assert_eq!(Some(Move::new(E2, E4, Flags::PAWN_SPRINT)), b.last_move);
+        assert_eq!(3, b.halfmove_clock());
+        assert_eq!(27, b.fullmove_number());
+    }
+
+    #[test]
+    fn test_en_passant_from_fen() {
+        let b = Board::from_fen("4k3/8/8/2Pp4/8/8/8/4K3 w - d6 0 1").unwrap();
+
+        let moves = b.generate_moves();
+        assert!(moves.contains(&Move::new(C5, D6, Flags::EP_CAPTURE)));
+    }
+
+    #[test]
+    fn test_counters_optional() {
+        let b = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - -").unwrap();
+
+        assert_eq!(0, b.halfmove_clock());
+        assert_eq!(1, b.fullmove_number());
+    }
+
+    #[test]
+    fn test_errors() {
+        for (fen, err) in [
+            ("", FenError::WrongFieldCount(0)),
+            ("8/8/8/8/8/8/8/8 w - - 0", FenError::WrongFieldCount(5)),
+            ("8/8/8/8/8/8/8 w - - 0 1", FenError::WrongRankCount(7)),
+            (
+                "8/8/8/8/8/8/8/7 w - - 0 1",
+                FenError::WrongRankLength { rank: 1 },
+            ),
+            ("9/8/8/8/8/8/8/8 w - - 0 1", FenError::InvalidPiece('9')),
+            (
+                "ppppppppp/8/8/8/8/8/8/8 w - - 0 1",
+                FenError::WrongRankLength { rank: 8 },
+            ),
+            ("8/8/8/4x3/8/8/8/8 w - - 0 1", FenError::InvalidPiece('x')),
+            (
+                "8/8/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::WrongKingCount {
+                    color: Color::Black,
+                    count: 0,
+                },
+            ),
+            (
+                "kk6/8/8/8/8/8/8/KK6 w - - 0 1",
+                FenError::WrongKingCount {
+                    color: Color::White,
+                    count: 2,
+                },
+            ),
+            (
+                "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::PawnOnBackRank(A8),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/p3K3 w - - 0 1",
+                FenError::PawnOnBackRank(A1),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+                FenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w KK - 0 1",
+                FenError::InvalidCastlingRights("KK".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w X - 0 1",
+                FenError::InvalidCastlingRights("X".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - e9 0 1",
+                FenError::InvalidEnPassant("e9".to_string()),
+            ),
+            (
+                "4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1",
+                FenError::InvalidEnPassant("e3".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 b - e3 0 1",
+                FenError::InvalidEnPassant("e3".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+                FenError::InvalidHalfmoveClock("x".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+                FenError::InvalidFullmoveNumber("0".to_string()),
+            ),
+        ] {
+            assert_eq!(Err(err), Board::from_fen(fen), "{}", fen);
+        }
+    }
+}
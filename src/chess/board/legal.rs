@@ -0,0 +1,254 @@
+use crate::chess::board::attacks;
+use crate::chess::board::bitboard::Bitboard;
+use crate::chess::board::move_list::MoveList;
+use crate::chess::board::piece::{Color, Kind};
+use crate::chess::board::square::Square;
+use crate::chess::board::Board;
+
+/// The pieces pinned to their king, and for each of them the squares it may
+/// still move to, i.e. the line between the king and the pinner, including the
+/// pinner itself.
+struct Pins {
+    pinned: Bitboard,
+    rays: [Bitboard; 64],
+}
+
+impl Board {
+    /// Generates the strictly legal moves of the side to move.
+    ///
+    /// Instead of making every move and testing whether the own king is left in check,
+    /// the checking and pinned pieces are determined once and only the moves that
+    /// respect them are kept. Use [`Board::generate_moves`] for pseudo-legal moves.
+    pub fn legal_moves(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        self.legal_moves_into(&mut moves);
+        moves
+    }
+
+    /// Like [`Board::legal_moves`], but generates into `moves`, which is cleared first.
+    pub fn legal_moves_into(&self, moves: &mut MoveList) {
+        self.generate_moves_into(moves);
+        let color = self.side_to_move;
+        let king = match self.find_king(color) {
+            // without a king there is nothing to leave in check
+            None => return,
+            Some(v) => v,
+        };
+
+        let checkers = self.attackers(king, color.other(), self.occupied());
+        // the squares a move other than a king move has to land on, which
+        // resolve a check by capturing the checker or blocking its line
+        let evasion_mask = match checkers.first() {
+            None => Bitboard::FULL,
+            Some(checker) if checkers.count() == 1 => attacks::between(king, checker) | checkers,
+            // double check, only the king can move
+            Some(_) => Bitboard::EMPTY,
+        };
+        let pins = self.pins(king, color);
+
+        moves.retain(|mov| {
+            if mov.from() == king {
+                // castling is only generated if the king doesn't pass or land on
+                // an attacked square, so only normal king moves are left to check
+                return mov.is_castle()
+                    || !self.is_square_attacked_ignoring(mov.to(), color.other(), king);
+            }
+
+            if mov.is_en_passant() {
+                // en passant removes two pieces from the same rank, which can
+                // expose the king in ways that pins don't cover, so look for
+                // attackers on the board as it is after the capture
+                let captured = Square::from_coordinates(mov.from().rank(), mov.to().file());
+                let mut occupied = self.occupied();
+                occupied.clear(mov.from());
+                occupied.clear(captured);
+                occupied.set(mov.to());
+                return self.attackers(king, color.other(), occupied).is_empty();
+            }
+
+            if !evasion_mask.contains(mov.to()) {
+                return false;
+            }
+
+            !pins.pinned.contains(mov.from()) || pins.rays[mov.from() as usize].contains(mov.to())
+        });
+    }
+
+    /// The pieces of `color` that are pinned to their king on `king`.
+    fn pins(&self, king: Square, color: Color) -> Pins {
+        let by = color.other();
+        let theirs = self.color_bitboard(by);
+        let diagonal = self.kind_bitboard(Kind::Bishop) | self.kind_bitboard(Kind::Queen);
+        let orthogonal = self.kind_bitboard(Kind::Rook) | self.kind_bitboard(Kind::Queen);
+
+        // the sliders of `by` that would attack the king if only their own
+        // pieces blocked them, i.e. they see through the pieces of `color`
+        let pinners = ((attacks::bishop(king, theirs) & diagonal)
+            | (attacks::rook(king, theirs) & orthogonal))
+            & theirs;
+
+        let mut pins = Pins {
+            pinned: Bitboard::EMPTY,
+            rays: [Bitboard::EMPTY; 64],
+        };
+        for pinner in pinners {
+            let line = attacks::between(king, pinner);
+            let blockers = line & self.occupied();
+            // with no piece in between, the pinner gives check instead
+            if let (1, Some(pinned)) = (blockers.count(), blockers.first()) {
+                pins.pinned.set(pinned);
+                pins.rays[pinned as usize] = line | Bitboard::from(pinner);
+            }
+        }
+        pins
+    }
+
+    /// Like [`Board::is_square_attacked`], but sliding pieces see through the
+    /// `ignored` square. This is needed for king moves, as the king must not
+    /// step back along the ray of a sliding piece that is checking it.
+    fn is_square_attacked_ignoring(&self, square: Square, by: Color, ignored: Square) -> bool {
+        let mut occupied = self.occupied();
+        occupied.clear(ignored);
+        !self.attackers(square, by, occupied).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::r#move::{Flags, Move};
+    use crate::chess::board::setup::default_setup;
+    use crate::chess::board::square::Square::*;
+
+    /// The moves that remain after making every pseudo-legal move and
+    /// dropping those that leave the own king in check.
+    fn filtered_pseudo_legal_moves(b: &mut Board) -> Vec<Move> {
+        let color = b.side_to_move();
+        let mut result = Vec::new();
+        for mov in b.generate_moves() {
+            let modification = b.make_move(mov);
+            if !b.king_in_check(color) {
+                result.push(mov);
+            }
+            b.unmake_move(modification);
+        }
+        result
+    }
+
+    fn assert_legal_moves_match(b: &mut Board, depth: usize) {
+        let legal = b.legal_moves();
+        let expected = filtered_pseudo_legal_moves(b);
+        assert_eq!(expected.len(), legal.len(), "{}", b.to_fen());
+        assert!(expected.iter().all(|m| legal.contains(m)), "{}", b.to_fen());
+
+        if depth > 1 {
+            for mov in legal {
+                let modification = b.make_move(mov);
+                assert_legal_moves_match(b, depth - 1);
+                b.unmake_move(modification);
+            }
+        }
+    }
+
+    #[test]
+    fn test_legal_moves_default_setup() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+
+        assert_eq!(20, b.legal_moves().len());
+    }
+
+    #[test]
+    fn test_legal_moves_match_filtered_pseudo_legal_moves() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+        ] {
+            let mut b = Board::from_fen(fen).unwrap();
+            assert_legal_moves_match(&mut b, 2);
+        }
+    }
+
+    #[test]
+    fn test_legal_moves_pinned_piece() {
+        // the knight is pinned and can't move at all, the rook can only move along the pin
+        let b = Board::from_fen("4r2k/8/8/8/4R2b/8/5N2/4K3 w - - 0 1").unwrap();
+        let moves = b.legal_moves();
+
+        assert!(moves.iter().all(|m| m.from() != F2));
+        assert!(moves
+            .iter()
+            .filter(|m| m.from() == E4)
+            .all(|m| m.to().file() == 5));
+        assert!(moves.contains(&Move::new(E4, E8, Flags::CAPTURE)));
+        assert!(moves.contains(&Move::new(E4, E3, Flags::QUIET)));
+    }
+
+    #[test]
+    fn test_pins() {
+        // the bishop on d1 is pinned by the queen, the knight on f2 by the
+        // bishop, and the rook on e4 can't be pinned through the knight on e3
+        let b = Board::from_fen("4r2k/8/8/8/4R2b/4N3/5N2/q2BK3 w - - 0 1").unwrap();
+        let pins = b.pins(E1, Color::White);
+
+        assert_eq!(Bitboard::from_iter([D1, F2]), pins.pinned);
+        assert_eq!(
+            Bitboard::from_iter([A1, B1, C1, D1]),
+            pins.rays[D1 as usize]
+        );
+        assert_eq!(Bitboard::from_iter([F2, G3, H4]), pins.rays[F2 as usize]);
+    }
+
+    #[test]
+    fn test_legal_moves_single_check() {
+        // the rook checks, so we can block, capture it or move the king
+        let b = Board::from_fen("4r2k/8/8/8/8/1B6/8/R3K3 w - - 0 1").unwrap();
+        let moves = b.legal_moves();
+
+        assert!(moves.contains(&Move::new(B3, E6, Flags::QUIET)));
+        assert!(!moves.contains(&Move::new(A1, A8, Flags::QUIET)));
+        assert!(moves.contains(&Move::new(E1, D1, Flags::QUIET)));
+        assert!(!moves.contains(&Move::new(E1, E2, Flags::QUIET)));
+        assert!(!moves.contains(&Move::new(A1, B1, Flags::QUIET)));
+        assert!(moves
+            .iter()
+            .filter(|m| m.from() != E1)
+            .all(|m| m.to().file() == 5));
+    }
+
+    #[test]
+    fn test_legal_moves_double_check() {
+        let b = Board::from_fen("4r2k/8/8/8/8/3n4/8/R3K3 w - - 0 1").unwrap();
+        let moves = b.legal_moves();
+
+        assert!(moves.iter().all(|m| m.from() == E1));
+        assert!(!moves.contains(&Move::new(E1, E2, Flags::QUIET)));
+        assert!(!moves.contains(&Move::new(E1, F2, Flags::QUIET)));
+        assert!(moves.contains(&Move::new(E1, D1, Flags::QUIET)));
+    }
+
+    #[test]
+    fn test_legal_moves_king_cant_retreat_along_check() {
+        let b = Board::from_fen("7k/8/8/8/8/8/8/r3K3 w - - 0 1").unwrap();
+        let moves = b.legal_moves();
+
+        assert!(!moves.contains(&Move::new(E1, F1, Flags::QUIET)));
+        assert!(!moves.contains(&Move::new(E1, D1, Flags::QUIET)));
+        assert_eq!(3, moves.len());
+    }
+
+    #[test]
+    fn test_legal_moves_en_passant_exposes_king() {
+        let b = Board::from_fen("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1").unwrap();
+
+        assert!(b
+            .generate_moves()
+            .contains(&Move::new(B5, C6, Flags::EP_CAPTURE)));
+        assert!(!b
+            .legal_moves()
+            .contains(&Move::new(B5, C6, Flags::EP_CAPTURE)));
+    }
+}
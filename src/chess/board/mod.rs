@@ -1,50 +1,117 @@
-use std::ops::{Index, IndexMut, Range};
+use std::ops::Index;
+use std::sync::Arc;
 
 use arr_macro::arr;
 
+use crate::chess::board::bitboard::Bitboard;
+use crate::chess::board::move_list::MoveList;
 use crate::chess::board::piece::{Color, Kind, Piece};
 use crate::chess::board::r#move::{Flags, Move};
 use crate::chess::board::square::Square::*;
 use crate::chess::board::square::{Direction, Square};
+use crate::chess::nnue::{Accumulator, Network};
 
+pub mod attacks;
+pub mod bitboard;
+pub mod fen;
+pub mod legal;
 pub mod r#move;
+pub mod move_list;
+pub mod outcome;
 pub mod piece;
+pub mod san;
+pub mod see;
 pub mod setup;
 pub mod square;
+pub mod uci;
+pub mod zobrist;
 
 const BOARD_SIZE: usize = 64;
 
+/// Which of the pseudo-legal moves to generate, so that a search can generate
+/// the moves it is most interested in first.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MoveFilter {
+    All,
+    /// Captures, including en passant and promotions that capture.
+    Captures,
+    /// Promotions that don't capture.
+    Promotions,
+    /// Everything that is neither a capture nor a promotion.
+    Quiets,
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct MakeMoveModification {
     made_move: Move,
     last_move: Option<Move>,
+    halfmove_clock_before: u16,
+    fullmove_number_before: u16,
+    zobrist_key_before: u64,
     taken_piece: Option<Piece>,
+    taken_square: Square,
+    castle_rook_move: Option<(Square, Square)>,
     castle_rights_white_kingside_before: bool,
     castle_rights_white_queenside_before: bool,
     castle_rights_black_kingside_before: bool,
     castle_rights_black_queenside_before: bool,
 }
 
-#[derive(Clone, Eq, PartialEq, Debug)]
+/// Boards are equal if their positions are, no matter whether they have a
+/// network, see [`Board::set_network`].
+#[derive(Clone, Debug)]
 pub struct Board {
     squares: [Option<Piece>; BOARD_SIZE],
+    kind_bitboards: [Bitboard; 6],
+    color_bitboards: [Bitboard; 2],
     last_move: Option<Move>,
     castle_rights_white_kingside: bool,
     castle_rights_white_queenside: bool,
     castle_rights_black_kingside: bool,
     castle_rights_black_queenside: bool,
+    side_to_move: Color,
+    halfmove_clock: u16,
+    fullmove_number: u16,
+    zobrist_key: u64,
+    /// Boxed, so that boards without a network stay small.
+    accumulator: Option<Box<Accumulator>>,
+}
+
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.squares == other.squares
+            && self.last_move == other.last_move
+            && self.castle_rights_white_kingside == other.castle_rights_white_kingside
+            && self.castle_rights_white_queenside == other.castle_rights_white_queenside
+            && self.castle_rights_black_kingside == other.castle_rights_black_kingside
+            && self.castle_rights_black_queenside == other.castle_rights_black_queenside
+            && self.side_to_move == other.side_to_move
+            && self.halfmove_clock == other.halfmove_clock
+            && self.fullmove_number == other.fullmove_number
+    }
 }
 
+impl Eq for Board {}
+
 impl Board {
     pub fn new() -> Self {
-        Self {
+        let mut b = Self {
             squares: arr![None; 64], // need integer literal here
+            kind_bitboards: [Bitboard::EMPTY; 6],
+            color_bitboards: [Bitboard::EMPTY; 2],
             last_move: None,
             castle_rights_white_kingside: true,
             castle_rights_white_queenside: true,
             castle_rights_black_kingside: true,
             castle_rights_black_queenside: true,
-        }
+            side_to_move: Color::White,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            zobrist_key: 0,
+            accumulator: None,
+        };
+        b.zobrist_key = b.compute_zobrist_key();
+        b
     }
 
     pub fn populate<S>(&mut self, setup: S)
@@ -55,42 +122,149 @@ impl Board {
     }
 
     pub fn place(&mut self, square: Square, piece: Piece) {
-        self[square] = Some(piece);
+        // whether en passant is possible can change with the pawns next to it
+        self.zobrist_key ^= self.state_key();
+        self.set_square(square, Some(piece));
+        self.zobrist_key ^= self.state_key();
+    }
+
+    /// Removes the piece on the given square, if any, and returns it.
+    pub fn remove(&mut self, square: Square) -> Option<Piece> {
+        let piece = self[square];
+        self.zobrist_key ^= self.state_key();
+        self.set_square(square, None);
+        self.zobrist_key ^= self.state_key();
+        piece
+    }
+
+    /// Every change of a square goes through here, so that the bitboards, the
+    /// Zobrist key and the accumulator stay up to date.
+    fn set_square(&mut self, square: Square, piece: Option<Piece>) {
+        if let Some(old) = self.squares[square as usize] {
+            self.kind_bitboards[old.kind() as usize].clear(square);
+            self.color_bitboards[old.color() as usize].clear(square);
+            self.zobrist_key ^= zobrist::piece_key(old, square);
+            if let Some(accumulator) = &mut self.accumulator {
+                accumulator.remove(old, square);
+            }
+        }
+        if let Some(new) = piece {
+            self.kind_bitboards[new.kind() as usize].set(square);
+            self.color_bitboards[new.color() as usize].set(square);
+            self.zobrist_key ^= zobrist::piece_key(new, square);
+            if let Some(accumulator) = &mut self.accumulator {
+                accumulator.add(new, square);
+            }
+        }
+        self.squares[square as usize] = piece;
+    }
+
+    /// Evaluates the position with the given network from now on, or with none.
+    /// The accumulator is computed once and then updated with every move.
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.accumulator = network.map(|network| Box::new(Accumulator::new(network, self)));
+    }
+
+    /// The hidden layer of the network for this position, if there is one.
+    pub fn accumulator(&self) -> Option<&Accumulator> {
+        self.accumulator.as_deref()
+    }
+
+    /// The squares of all pieces of the given color and kind.
+    pub fn bitboard(&self, color: Color, kind: Kind) -> Bitboard {
+        self.kind_bitboards[kind as usize] & self.color_bitboards[color as usize]
+    }
+
+    /// The squares of all pieces of the given kind, no matter the color.
+    pub fn kind_bitboard(&self, kind: Kind) -> Bitboard {
+        self.kind_bitboards[kind as usize]
+    }
+
+    /// The squares of all pieces of the given color.
+    pub fn color_bitboard(&self, color: Color) -> Bitboard {
+        self.color_bitboards[color as usize]
+    }
+
+    /// The squares of all pieces on the board.
+    pub fn occupied(&self) -> Bitboard {
+        self.color_bitboards[0] | self.color_bitboards[1]
+    }
+
+    /// The move that led to this position, if it was made on this board.
+    pub fn last_move(&self) -> Option<Move> {
+        self.last_move
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    pub fn halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u16 {
+        self.fullmove_number
     }
 
     pub fn pieces_with_position(&self) -> Vec<(Square, Piece)> {
-        self.squares
-            .iter()
-            .enumerate()
-            .filter(|(_, x)| x.is_some())
-            .map(|(i, x)| (Square::from(i), x.unwrap()))
+        self.occupied()
+            .into_iter()
+            .map(|s| (s, self[s].unwrap()))
             .collect()
     }
 
     pub fn pieces(&self) -> Vec<Piece> {
-        #[allow(clippy::option_filter_map)] // with flatten we get Vec<&Piece>
-        self.squares
-            .iter()
-            .filter(|x| x.is_some())
-            .map(|x| x.unwrap())
+        self.occupied()
+            .into_iter()
+            .map(|s| self[s].unwrap())
             .collect()
     }
 
     pub fn unmake_move(&mut self, mov: MakeMoveModification) {
         self.last_move = mov.last_move;
-        self[mov.made_move.from()] = self[mov.made_move.to()];
-        self[mov.made_move.to()] = mov.taken_piece;
+        self.halfmove_clock = mov.halfmove_clock_before;
+        self.fullmove_number = mov.fullmove_number_before;
+        self.side_to_move = self.side_to_move.other();
+        let piece = match self[mov.made_move.to()] {
+            Some(p) if mov.made_move.is_promotion() => Some(Piece::new(p.color(), Kind::Pawn)),
+            p => p,
+        };
+        self.set_square(mov.made_move.from(), piece);
+        self.set_square(mov.made_move.to(), None);
+        self.set_square(mov.taken_square, mov.taken_piece);
+        if let Some((rook_from, rook_to)) = mov.castle_rook_move {
+            self.set_square(rook_from, self[rook_to]);
+            self.set_square(rook_to, None);
+        }
         self.castle_rights_white_kingside = mov.castle_rights_white_kingside_before;
         self.castle_rights_white_queenside = mov.castle_rights_white_queenside_before;
         self.castle_rights_black_kingside = mov.castle_rights_black_kingside_before;
         self.castle_rights_black_queenside = mov.castle_rights_black_queenside_before;
+        self.zobrist_key = mov.zobrist_key_before;
     }
 
     pub fn make_move(&mut self, mov: Move) -> MakeMoveModification {
+        let castle_rook_move = if mov.is_castle() {
+            Some(Board::castle_rook_move(mov.to()))
+        } else {
+            None
+        };
+        // the pawn taken en passant is not on the target square, but next to the capturing pawn
+        let taken_square = if mov.is_en_passant() {
+            Square::from_coordinates(mov.from().rank(), mov.to().file())
+        } else {
+            mov.to()
+        };
         let original_state = MakeMoveModification {
-            made_move: mov.clone(),
-            last_move: self.last_move.clone(),
-            taken_piece: self[mov.to()],
+            made_move: mov,
+            last_move: self.last_move,
+            halfmove_clock_before: self.halfmove_clock,
+            fullmove_number_before: self.fullmove_number,
+            zobrist_key_before: self.zobrist_key,
+            taken_piece: self[taken_square],
+            taken_square,
+            castle_rook_move,
             castle_rights_white_kingside_before: self.castle_rights_white_kingside,
             castle_rights_white_queenside_before: self.castle_rights_white_queenside,
             castle_rights_black_kingside_before: self.castle_rights_black_kingside,
@@ -103,6 +277,10 @@ impl Board {
         };
         let color = piece.color();
 
+        // castle rights, en passant and side to move are taken out of the key
+        // here and put back in once the move is made
+        self.zobrist_key ^= self.state_key();
+
         let (r1, r2) = match color {
             Color::Black => (A8, H8),
             Color::White => (A1, H1),
@@ -137,105 +315,185 @@ impl Board {
             }
         }
 
-        self[mov.from()] = None;
-        self[mov.to()] = Some(piece);
+        // capturing a rook on its original square takes away the opponent's castle rights
+        match mov.to() {
+            A1 => self.castle_rights_white_queenside = false,
+            H1 => self.castle_rights_white_kingside = false,
+            A8 => self.castle_rights_black_queenside = false,
+            H8 => self.castle_rights_black_kingside = false,
+            _ => {}
+        }
+
+        self.set_square(mov.from(), None);
+        self.set_square(taken_square, None);
+        let placed = match mov.promotion_kind() {
+            Some(kind) => Piece::new(color, kind),
+            None => piece,
+        };
+        self.set_square(mov.to(), Some(placed));
+        if let Some((rook_from, rook_to)) = castle_rook_move {
+            self.set_square(rook_to, self[rook_from]);
+            self.set_square(rook_from, None);
+        }
+
+        // the fifty-move rule only counts moves without captures or pawn moves;
+        // the counters stop at their maximum, which a FEN may already contain
+        if piece.kind() == Kind::Pawn || original_state.taken_piece.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        }
+        if self.side_to_move == Color::Black {
+            self.fullmove_number = self.fullmove_number.saturating_add(1);
+        }
+        self.side_to_move = self.side_to_move.other();
 
         self.last_move = Some(mov);
+        self.zobrist_key ^= self.state_key();
+        debug_assert_eq!(
+            self.compute_zobrist_key(),
+            self.zobrist_key,
+            "incremental zobrist key diverged"
+        );
         original_state
     }
 
+    /// The squares the rook moves from and to when the king castles to the given square.
+    fn castle_rook_move(king_target: Square) -> (Square, Square) {
+        match king_target {
+            G1 => (H1, F1),
+            C1 => (A1, D1),
+            G8 => (H8, F8),
+            C8 => (A8, D8),
+            _ => panic!("{} is not a castling target square", king_target),
+        }
+    }
+
     pub fn king_in_check(&self, color: Color) -> bool {
         let square = match self.find_king(color) {
             None => return false,
             Some(v) => v,
         };
 
-        self.is_king_in_check_by_sliding(color, square)
-            || self.is_king_in_check_by_knights(color, square)
-            || self.is_king_in_check_by_pawns(color, square)
+        self.is_square_attacked(square, color.other())
     }
 
-    fn is_king_in_check_by_pawns(&self, color: Color, square: Square) -> bool {
-        // detect check by pawn
-        let (l, r) = match color {
-            Color::Black => (Direction::DownLeft, Direction::DownRight),
-            Color::White => (Direction::UpLeft, Direction::UpRight),
-        };
-        for dir in [l, r] {
-            if Board::within_board_bounds(square, dir) {
-                if let Some(piece) = self[square + dir] {
-                    if piece.color() == color.other() && piece.kind() == Kind::Pawn {
-                        return true;
-                    }
-                }
-            }
-        }
-        false
+    /// Whether any piece of the given color attacks the square, no matter
+    /// whether the square is empty or occupied.
+    pub fn is_square_attacked(&self, square: Square, by: Color) -> bool {
+        !self.attackers(square, by, self.occupied()).is_empty()
     }
 
-    fn is_king_in_check_by_knights(&self, color: Color, square: Square) -> bool {
-        // detect check by knight
-        let mut knight_moves = Vec::new();
-        self.generate_moves_knight(&mut knight_moves, color, square);
-        knight_moves.into_iter().map(|m| m.to()).any(|s| {
-            self[s].map_or(false, |p| {
-                p.kind() == Kind::Knight && p.color() == color.other()
-            })
-        })
+    /// All pieces of color `by` that attack the square, with sliding pieces
+    /// being blocked by the pieces in `occupied` instead of the actual board.
+    pub(crate) fn attackers(&self, square: Square, by: Color, occupied: Bitboard) -> Bitboard {
+        let diagonal = self.kind_bitboard(Kind::Bishop) | self.kind_bitboard(Kind::Queen);
+        let orthogonal = self.kind_bitboard(Kind::Rook) | self.kind_bitboard(Kind::Queen);
+
+        // a pawn of `by` attacks the square if a pawn of the other color on
+        // the square would attack that pawn
+        let attackers = (attacks::pawn(by.other(), square) & self.kind_bitboard(Kind::Pawn))
+            | (attacks::knight(square) & self.kind_bitboard(Kind::Knight))
+            | (attacks::king(square) & self.kind_bitboard(Kind::King))
+            | (attacks::bishop(square, occupied) & diagonal)
+            | (attacks::rook(square, occupied) & orthogonal);
+        attackers & self.color_bitboard(by) & occupied
     }
 
-    fn is_king_in_check_by_sliding(&self, color: Color, square: Square) -> bool {
-        // detect check by rook, bishop or queen
-        for kind in [Kind::Rook, Kind::Bishop] {
-            let mut sliding_moves = Vec::new();
-            self.generate_moves_sliding(&mut sliding_moves, color, square, kind);
+    fn find_king(&self, color: Color) -> Option<Square> {
+        self.bitboard(color, Kind::King).first()
+    }
 
-            if sliding_moves
-                .into_iter()
-                .map(|m| m.to())
-                .filter_map(|s| self[s])
-                .any(|p| {
-                    p.color() == color.other()
-                        && match p.kind() {
-                            Kind::Queen => true,
-                            k => k == kind,
-                        }
-                })
-            {
-                return true;
-            }
-        }
-        false
+    /// Generates the pseudo-legal moves of the side to move.
+    pub fn generate_moves(&self) -> MoveList {
+        self.generate_moves_for(self.side_to_move)
     }
 
-    fn find_king(&self, color: Color) -> Option<Square> {
-        for s in Square::ALL {
-            if let Some(p) = self[s] {
-                if p.kind() == Kind::King && p.color() == color {
-                    return Some(s);
-                }
+    /// Generates the pseudo-legal moves of the side to move into `moves`,
+    /// which is cleared first. This allows reusing one list for many positions.
+    pub fn generate_moves_into(&self, moves: &mut MoveList) {
+        moves.clear();
+        self.generate_moves_for_into(self.side_to_move, moves, MoveFilter::All);
+    }
+
+    /// Generates the pseudo-legal moves of the given color, no matter whose turn it is.
+    pub fn generate_moves_for(&self, color: Color) -> MoveList {
+        let mut moves = MoveList::new();
+        self.generate_moves_for_into(color, &mut moves, MoveFilter::All);
+        moves
+    }
+
+    /// Generates the pseudo-legal captures of the side to move, including
+    /// en passant and promotions that capture.
+    pub fn generate_captures(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        self.generate_captures_into(&mut moves);
+        moves
+    }
+
+    /// Like [`Board::generate_captures`], but generates into `moves`, which is cleared first.
+    pub fn generate_captures_into(&self, moves: &mut MoveList) {
+        self.generate_filtered_into(MoveFilter::Captures, moves);
+    }
+
+    /// Generates the pseudo-legal moves of the side to move that pass `filter`
+    /// into `moves`, which is cleared first.
+    pub fn generate_filtered_into(&self, filter: MoveFilter, moves: &mut MoveList) {
+        moves.clear();
+        self.generate_moves_for_into(self.side_to_move, moves, filter);
+    }
+
+    /// Whether `mov` is one of the pseudo-legal moves of the side to move,
+    /// without generating the moves of all pieces. This checks moves that
+    /// were found in other positions, like the ones of a transposition table.
+    pub fn is_pseudo_legal(&self, mov: Move) -> bool {
+        let piece = match self[mov.from()] {
+            Some(p) if p.color() == self.side_to_move => p,
+            _ => return false,
+        };
+        let mut moves = MoveList::new();
+        let (color, square) = (piece.color(), mov.from());
+        self.generate_moves_piece(&mut moves, color, square, piece.kind(), MoveFilter::All);
+        moves.contains(&mov)
+    }
+
+    fn generate_moves_for_into(&self, color: Color, moves: &mut MoveList, filter: MoveFilter) {
+        let kinds: &[Kind] = match filter {
+            MoveFilter::Promotions => &[Kind::Pawn],
+            _ => &Kind::ALL,
+        };
+        for &kind in kinds {
+            for square in self.bitboard(color, kind) {
+                self.generate_moves_piece(moves, color, square, kind, filter);
             }
         }
-        None
     }
 
-    pub fn generate_moves(&mut self, color: Color) -> Vec<Move> {
-        let mut moves = Vec::new();
-        self.pieces_with_position()
-            .into_iter()
-            .filter(|(_, x)| x.color() == color)
-            .for_each(|(i, x)| match x.kind() {
-                Kind::Pawn => self.generate_moves_pawn(&mut moves, color, i),
-                Kind::King => self.generate_moves_king(&mut moves, color, i),
-                Kind::Knight => self.generate_moves_knight(&mut moves, color, i),
-                Kind::Bishop => self.generate_moves_bishop(&mut moves, color, i),
-                Kind::Rook => self.generate_moves_rook(&mut moves, color, i),
-                Kind::Queen => self.generate_moves_queen(&mut moves, color, i),
-            });
-        moves
+    fn generate_moves_piece(
+        &self,
+        moves: &mut MoveList,
+        color: Color,
+        square: Square,
+        kind: Kind,
+        filter: MoveFilter,
+    ) {
+        match kind {
+            Kind::Pawn => self.generate_moves_pawn(moves, color, square, filter),
+            Kind::King => self.generate_moves_king(moves, color, square, filter),
+            Kind::Knight => self.generate_moves_knight(moves, color, square, filter),
+            Kind::Bishop => self.generate_moves_bishop(moves, color, square, filter),
+            Kind::Rook => self.generate_moves_rook(moves, color, square, filter),
+            Kind::Queen => self.generate_moves_queen(moves, color, square, filter),
+        }
     }
 
-    fn generate_moves_pawn(&self, result: &mut Vec<Move>, color: Color, square: Square) {
+    fn generate_moves_pawn(
+        &self,
+        result: &mut MoveList,
+        color: Color,
+        square: Square,
+        filter: MoveFilter,
+    ) {
         if square.rank() == 1 || square.rank() == 8 {
             return;
         }
@@ -244,9 +502,7 @@ impl Board {
             Color::Black => (Direction::Down, 7_u8, 2_u8),
             Color::White => (Direction::Up, 2_u8, 7_u8),
         };
-
-        // normal moves
-        if self[square + move_dir].is_none() {
+        let push_moves = |result: &mut MoveList, target: Square, flags: Flags| {
             if square.rank() == promotion_possible_rank {
                 for promotion_flags in [
                     Flags::PROMOTION_BISHOP,
@@ -254,128 +510,86 @@ impl Board {
                     Flags::PROMOTION_QUEEN,
                     Flags::PROMOTION_ROOK,
                 ] {
-                    result.push(Move::new(square, square + move_dir, promotion_flags));
+                    result.push(Move::new(square, target, flags | promotion_flags));
                 }
             } else {
-                let mov = Move::new(square, square + move_dir, Flags::QUIET);
-                result.push(mov);
+                result.push(Move::new(square, target, flags));
             }
+        };
+
+        // normal moves
+        let occupied = self.occupied();
+        let promotes = square.rank() == promotion_possible_rank;
+        let pushes = match filter {
+            MoveFilter::All => true,
+            MoveFilter::Captures => false,
+            MoveFilter::Promotions => promotes,
+            MoveFilter::Quiets => !promotes,
+        };
+        if pushes && !occupied.contains(square + move_dir) {
+            push_moves(result, square + move_dir, Flags::QUIET);
 
             // pawn sprint, but only if normal move is also possible
-            if square.rank() == home_rank && self[square + move_dir + move_dir].is_none() {
+            if square.rank() == home_rank && !occupied.contains(square + move_dir + move_dir) {
                 let sprint = Move::new(square, square + move_dir + move_dir, Flags::PAWN_SPRINT);
                 result.push(sprint);
             }
         }
 
-        // captures
-        let file = square.file();
-        let can_capture = |target: Square| match self[target] {
-            None => false,
-            Some(piece) => piece.color() == color.other(),
-        };
-        let generate_promotion_capture_moves = |target: Square| {
-            let mut capture_promotion_moves = Vec::new();
-
-            if square.rank() == promotion_possible_rank {
-                for promotion_flags in [
-                    Flags::PROMOTION_BISHOP,
-                    Flags::PROMOTION_KNIGHT,
-                    Flags::PROMOTION_QUEEN,
-                    Flags::PROMOTION_ROOK,
-                ] {
-                    capture_promotion_moves.push(Move::new(
-                        square,
-                        target,
-                        Flags::CAPTURE | promotion_flags,
-                    ));
-                }
-            } else {
-                let capture = Move::new(square, target, Flags::CAPTURE);
-                capture_promotion_moves.push(capture);
-            }
-
-            capture_promotion_moves
-        };
+        if !matches!(filter, MoveFilter::All | MoveFilter::Captures) {
+            return;
+        }
 
         // captures
-        let capture_dirs = match color {
-            Color::Black => [Direction::DownLeft, Direction::DownRight],
-            Color::White => [Direction::UpLeft, Direction::UpRight],
-        };
-        for dir in capture_dirs {
-            if Board::within_board_bounds(square, dir) && can_capture(square + dir) {
-                generate_promotion_capture_moves(square + dir)
-                    .into_iter()
-                    .for_each(|m| result.push(m));
-            }
+        let attacks = attacks::pawn(color, square);
+        for target in attacks & self.color_bitboard(color.other()) {
+            push_moves(result, target, Flags::CAPTURE);
         }
 
         // en passant
-        let ep_possible = match &self.last_move {
-            None => false,
-            Some(mov) => mov.is_pawn_sprint(),
-        };
-        if ep_possible {
-            let sprint_col = self.last_move.as_ref().unwrap().from().file();
-            if file == sprint_col - 1 || file == sprint_col + 1 {
-                let ep_base_row = match color {
-                    Color::Black => 4,
-                    Color::White => 5,
-                };
-                if square.rank() == ep_base_row {
-                    let ep_move = Move::new(
-                        square,
-                        Square::from_coordinates(
-                            match color {
-                                Color::Black => 3,
-                                Color::White => 6,
-                            },
-                            sprint_col,
-                        ),
-                        Flags::EP_CAPTURE,
-                    );
-                    result.push(ep_move);
-                }
+        if let Some(target) = self.en_passant_target() {
+            // only the opponent's sprint can be captured
+            let ep_target_rank = match color {
+                Color::Black => 3,
+                Color::White => 6,
+            };
+            if target.rank() == ep_target_rank && attacks.contains(target) {
+                result.push(Move::new(square, target, Flags::EP_CAPTURE));
             }
         }
     }
 
-    fn generate_moves_king(&self, result: &mut Vec<Move>, color: Color, square: Square) {
+    fn generate_moves_king(
+        &self,
+        result: &mut MoveList,
+        color: Color,
+        square: Square,
+        filter: MoveFilter,
+    ) {
         // moves and captures
-        for direction in [
-            Direction::Up,
-            Direction::Down,
-            Direction::Left,
-            Direction::Right,
-            Direction::UpLeft,
-            Direction::UpRight,
-            Direction::DownLeft,
-            Direction::DownRight,
-        ] {
-            if !Board::within_board_bounds(square, direction) {
-                continue;
-            }
-            if let Some(mov) = match self[square + direction] {
-                None => Some(Move::new(square, square + direction, Flags::QUIET)),
-                Some(p) if p.color() == color.other() => {
-                    Some(Move::new(square, square + direction, Flags::CAPTURE))
-                }
-                Some(_) => None,
-            } {
-                result.push(mov);
-            }
-        }
+        self.push_moves(result, color, square, attacks::king(square), filter);
 
-        if color.king_square() == square && self.has_castle_rights(color) {
+        if matches!(filter, MoveFilter::All | MoveFilter::Quiets)
+            && color.king_square() == square
+            && self.has_castle_rights(color)
+            && !self.is_square_attacked(square, color.other())
+        {
             // we can assume that the king is on its original square
             let base_row = square.rank();
-
-            let is_kingside_rook_present = self[Square::from_coordinates(base_row, 8)]
-                .map_or(false, |p| p.kind() == Kind::Rook && p.color() == color);
-            let is_kingside_free = self[square + Direction::Right].is_none()
-                && self[square + Direction::Right + Direction::Right].is_none();
-            if is_kingside_rook_present && is_kingside_free {
+            let is_rook_present = |file: u8| {
+                self.bitboard(color, Kind::Rook)
+                    .contains(Square::from_coordinates(base_row, file))
+            };
+            // the king may neither pass through nor land on an attacked square
+            let occupied = self.occupied();
+            let is_free_and_safe =
+                |s: Square| !occupied.contains(s) && !self.is_square_attacked(s, color.other());
+
+            if self.has_kingside_castle_rights(color)
+                && is_rook_present(8)
+                && is_free_and_safe(square + Direction::Right)
+                && is_free_and_safe(square + Direction::Right + Direction::Right)
+            {
                 let kingside_castle = Move::new(
                     square,
                     square + Direction::Right + Direction::Right,
@@ -384,109 +598,115 @@ impl Board {
                 result.push(kingside_castle);
             }
 
-            let is_queenside_rook_present = self[Square::from_coordinates(base_row, 1)]
-                .map_or(false, |p| p.kind() == Kind::Rook && p.color() == color);
-            let is_queenside_free = self[square + Direction::Left].is_none()
-                && self[square + Direction::Left + Direction::Left].is_none();
-            if is_queenside_rook_present && is_queenside_free {
+            if self.has_queenside_castle_rights(color)
+                && is_rook_present(1)
+                && is_free_and_safe(square + Direction::Left)
+                && is_free_and_safe(square + Direction::Left + Direction::Left)
+                // the rook passes this square, so it only has to be empty
+                && !occupied.contains(square + Direction::Left + Direction::Left + Direction::Left)
+            {
                 let queenside_castle = Move::new(
                     square,
                     square + Direction::Left + Direction::Left,
-                    Flags::CASTLE_KING,
+                    Flags::CASTLE_QUEEN,
                 );
                 result.push(queenside_castle);
             }
         }
     }
 
-    fn generate_moves_bishop(&self, result: &mut Vec<Move>, color: Color, square: Square) {
-        self.generate_moves_sliding(result, color, square, Kind::Bishop)
+    fn generate_moves_bishop(
+        &self,
+        result: &mut MoveList,
+        color: Color,
+        square: Square,
+        filter: MoveFilter,
+    ) {
+        self.generate_moves_sliding(result, color, square, Kind::Bishop, filter)
     }
 
-    fn generate_moves_rook(&self, result: &mut Vec<Move>, color: Color, square: Square) {
-        self.generate_moves_sliding(result, color, square, Kind::Rook)
+    fn generate_moves_rook(
+        &self,
+        result: &mut MoveList,
+        color: Color,
+        square: Square,
+        filter: MoveFilter,
+    ) {
+        self.generate_moves_sliding(result, color, square, Kind::Rook, filter)
     }
 
-    fn generate_moves_queen(&self, result: &mut Vec<Move>, color: Color, square: Square) {
-        self.generate_moves_sliding(result, color, square, Kind::Queen)
+    fn generate_moves_queen(
+        &self,
+        result: &mut MoveList,
+        color: Color,
+        square: Square,
+        filter: MoveFilter,
+    ) {
+        self.generate_moves_sliding(result, color, square, Kind::Queen, filter)
     }
 
     fn generate_moves_sliding(
         &self,
-        result: &mut Vec<Move>,
+        result: &mut MoveList,
         color: Color,
         square: Square,
         kind: Kind,
+        filter: MoveFilter,
     ) {
-        let directions = [
-            Direction::Up,
-            Direction::Down,
-            Direction::Left,
-            Direction::Right,
-            Direction::UpLeft,
-            Direction::UpRight,
-            Direction::DownLeft,
-            Direction::DownRight,
-        ];
-        let r: Range<usize> = match kind {
-            Kind::Bishop => 4..8,
-            Kind::Rook => 0..4,
-            Kind::Queen => 0..8,
+        let occupied = self.occupied();
+        let attacks = match kind {
+            Kind::Bishop => attacks::bishop(square, occupied),
+            Kind::Rook => attacks::rook(square, occupied),
+            Kind::Queen => attacks::queen(square, occupied),
             _ => panic!("{:?} @ {} is not a sliding piece", kind, square),
         };
-        for &dir in &directions[r] {
-            let mut current_square = square;
-            while Board::within_board_bounds(current_square, dir) {
-                let target_square = current_square + dir;
-                match self[target_square] {
-                    None => {
-                        let mov = Move::new(square, target_square, Flags::QUIET);
-                        result.push(mov);
-                    }
-                    Some(p) => {
-                        if p.color() == color.other() {
-                            let mov = Move::new(square, target_square, Flags::CAPTURE);
-                            result.push(mov);
-                        }
-                        break;
-                    }
-                };
+        self.push_moves(result, color, square, attacks, filter);
+    }
 
-                current_square = target_square;
-            }
-        }
+    fn generate_moves_knight(
+        &self,
+        result: &mut MoveList,
+        color: Color,
+        square: Square,
+        filter: MoveFilter,
+    ) {
+        self.push_moves(result, color, square, attacks::knight(square), filter);
     }
 
-    fn generate_moves_knight(&self, result: &mut Vec<Move>, color: Color, square: Square) {
-        let directions = [
-            Direction::UpUpLeft,
-            Direction::UpUpRight,
-            Direction::LeftLeftUp,
-            Direction::LeftLeftDown,
-            Direction::RightRightUp,
-            Direction::RightRightDown,
-            Direction::DownDownLeft,
-            Direction::DownDownRight,
-        ];
-
-        for dir in directions {
-            if Board::within_board_bounds(square, dir) {
-                match self[square + dir] {
-                    None => {
-                        let mov = Move::new(square, square + dir, Flags::QUIET);
-                        result.push(mov);
-                    }
-                    Some(p) => {
-                        if p.color() == color.other() {
-                            let mov = Move::new(square, square + dir, Flags::CAPTURE);
-                            result.push(mov);
-                        }
-                    }
-                };
-            }
+    /// Pushes a quiet move or capture from `square` to every attacked square
+    /// that isn't occupied by an own piece, or only the captures.
+    fn push_moves(
+        &self,
+        result: &mut MoveList,
+        color: Color,
+        square: Square,
+        attacks: Bitboard,
+        filter: MoveFilter,
+    ) {
+        let enemies = self.color_bitboard(color.other());
+        let targets = match filter {
+            MoveFilter::All => !self.color_bitboard(color),
+            MoveFilter::Captures => enemies,
+            MoveFilter::Promotions => Bitboard::EMPTY,
+            MoveFilter::Quiets => !self.occupied(),
+        };
+        for target in attacks & targets {
+            let flags = if enemies.contains(target) {
+                Flags::CAPTURE
+            } else {
+                Flags::QUIET
+            };
+            result.push(Move::new(square, target, flags));
         }
     }
 
+    /// The square a pawn can capture en passant on, if the last move was a pawn sprint.
+    fn en_passant_target(&self) -> Option<Square> {
+        self.last_move
+            .filter(|mov| mov.is_pawn_sprint())
+            .map(|mov| Square::from((mov.from() as usize + mov.to() as usize) / 2))
+    }
+
     fn within_board_bounds(square: Square, direction: Direction) -> bool {
         match direction {
             Direction::Up => square.rank() < 8,
@@ -510,9 +730,20 @@ impl Board {
     }
 
     pub fn has_castle_rights(&self, color: Color) -> bool {
+        self.has_kingside_castle_rights(color) || self.has_queenside_castle_rights(color)
+    }
+
+    pub fn has_kingside_castle_rights(&self, color: Color) -> bool {
         match color {
-            Color::Black => self.castle_rights_black_kingside || self.castle_rights_black_queenside,
-            Color::White => self.castle_rights_white_kingside || self.castle_rights_white_queenside,
+            Color::Black => self.castle_rights_black_kingside,
+            Color::White => self.castle_rights_white_kingside,
+        }
+    }
+
+    pub fn has_queenside_castle_rights(&self, color: Color) -> bool {
+        match color {
+            Color::Black => self.castle_rights_black_queenside,
+            Color::White => self.castle_rights_white_queenside,
         }
     }
 }
@@ -525,12 +756,6 @@ impl Index<Square> for Board {
     }
 }
 
-impl IndexMut<Square> for Board {
-    fn index_mut(&mut self, index: Square) -> &mut Self::Output {
-        &mut self.squares[index as usize]
-    }
-}
-
 impl Default for Board {
     fn default() -> Self {
         Self::new()
@@ -548,7 +773,7 @@ mod tests {
         let mut b = Board::new();
         b.populate(default_setup);
 
-        let moves = b.generate_moves(Color::White);
+        let moves = b.generate_moves_for(Color::White);
         assert_eq!(20, moves.len());
 
         for mov in moves {
@@ -567,7 +792,7 @@ mod tests {
         b.place(D5, Piece::new(Color::Black, Kind::Pawn));
 
         for color in [Color::White, Color::Black] {
-            let moves = b.generate_moves(color);
+            let moves = b.generate_moves_for(color);
             assert_eq!(2, moves.len());
 
             for mov in moves {
@@ -580,12 +805,187 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_make_unmake_castle() {
+        for (fen, mov, king, rook) in [
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                Move::new(E1, G1, Flags::CASTLE_KING),
+                G1,
+                F1,
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                Move::new(E1, C1, Flags::CASTLE_QUEEN),
+                C1,
+                D1,
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+                Move::new(E8, G8, Flags::CASTLE_KING),
+                G8,
+                F8,
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+                Move::new(E8, C8, Flags::CASTLE_QUEEN),
+                C8,
+                D8,
+            ),
+        ] {
+            let mut b = Board::from_fen(fen).unwrap();
+            let original = b.clone();
+            let color = b[mov.from()].unwrap().color();
+            let (rook_from, _) = Board::castle_rook_move(mov.to());
+
+            let res = b.make_move(mov);
+            assert_eq!(Some(Piece::new(color, Kind::King)), b[king]);
+            assert_eq!(Some(Piece::new(color, Kind::Rook)), b[rook]);
+            assert_eq!(None, b[color.king_square()]);
+            assert_eq!(None, b[rook_from]);
+            assert!(!b.has_castle_rights(color));
+            assert!(b.has_castle_rights(color.other()));
+
+            b.unmake_move(res);
+            assert_eq!(original, b);
+        }
+    }
+
+    #[test]
+    fn test_capture_rook_removes_castle_rights() {
+        let mut b = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let original = b.clone();
+
+        let res = b.make_move(Move::new(H1, H8, Flags::CAPTURE));
+        assert!(!b.castle_rights_white_kingside);
+        assert!(b.castle_rights_white_queenside);
+        assert!(!b.castle_rights_black_kingside);
+        assert!(b.castle_rights_black_queenside);
+
+        b.unmake_move(res);
+        assert_eq!(original, b);
+    }
+
+    #[test]
+    fn test_make_unmake_promotion() {
+        for (kind, flags) in [
+            (Kind::Knight, Flags::PROMOTION_KNIGHT),
+            (Kind::Bishop, Flags::PROMOTION_BISHOP),
+            (Kind::Rook, Flags::PROMOTION_ROOK),
+            (Kind::Queen, Flags::PROMOTION_QUEEN),
+        ] {
+            let mut b = Board::from_fen("3r4/2P5/8/8/8/8/8/K6k w - - 0 1").unwrap();
+            let original = b.clone();
+
+            for mov in [
+                Move::new(C7, C8, flags),
+                Move::new(C7, D8, Flags::CAPTURE | flags),
+            ] {
+                let res = b.make_move(mov);
+                assert_eq!(Some(Piece::new(Color::White, kind)), b[mov.to()]);
+                assert_eq!(None, b[C7]);
+                assert_eq!(if mov.is_capture() { 3 } else { 4 }, b.pieces().len());
+
+                b.unmake_move(res);
+                assert_eq!(original, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_promoted_piece_moves() {
+        let mut b = Board::from_fen("8/2P5/8/8/8/8/8/K6k w - - 0 1").unwrap();
+        b.make_move(Move::new(C7, C8, Flags::PROMOTION_QUEEN));
+
+        // 21 queen moves and 3 king moves
+        assert_eq!(24, b.generate_moves_for(Color::White).len());
+    }
+
+    #[test]
+    fn test_make_unmake_counters() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+        let original = b.clone();
+
+        let mut modifications = Vec::new();
+        for (mov, fen) in [
+            (
+                Move::new(E2, E4, Flags::PAWN_SPRINT),
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            ),
+            (
+                Move::new(E7, E5, Flags::PAWN_SPRINT),
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+            ),
+            (
+                Move::new(G1, F3, Flags::QUIET),
+                "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            ),
+            (
+                Move::new(B8, C6, Flags::QUIET),
+                "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            ),
+            (
+                Move::new(F3, E5, Flags::CAPTURE),
+                "r1bqkbnr/pppp1ppp/2n5/4N3/4P3/8/PPPP1PPP/RNBQKB1R b KQkq - 0 3",
+            ),
+        ] {
+            modifications.push(b.make_move(mov));
+            assert_eq!(fen, b.to_fen());
+        }
+
+        for modification in modifications.into_iter().rev() {
+            b.unmake_move(modification);
+        }
+        assert_eq!(original, b);
+    }
+
+    #[test]
+    fn test_make_unmake_counters_at_maximum() {
+        for (fen, mov, after) in [
+            (
+                "4k3/8/8/8/8/8/8/4K1N1 w - - 65535 65535",
+                Move::new(G1, F3, Flags::QUIET),
+                "4k3/8/8/8/8/5N2/8/4K3 b - - 65535 65535",
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 b - - 0 65535",
+                Move::new(E8, D8, Flags::QUIET),
+                "3k4/8/8/8/8/8/8/4K3 w - - 1 65535",
+            ),
+        ] {
+            let mut b = Board::from_fen(fen).unwrap();
+            let original = b.clone();
+
+            let modification = b.make_move(mov);
+            assert_eq!(after, b.to_fen());
+            b.unmake_move(modification);
+            assert_eq!(original, b);
+        }
+    }
+
+    #[test]
+    fn test_move_gen_side_to_move() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+        assert!(b
+            .generate_moves()
+            .iter()
+            .all(|m| b[m.from()].unwrap().color() == Color::White));
+
+        b.make_move(Move::new(E2, E4, Flags::PAWN_SPRINT));
+        assert!(b
+            .generate_moves()
+            .iter()
+            .all(|m| b[m.from()].unwrap().color() == Color::Black));
+    }
+
     #[test]
     fn test_move_gen_default_setup() {
         let mut b = Board::new();
         b.populate(default_setup);
 
-        assert_eq!(20, b.generate_moves(Color::White).len());
+        assert_eq!(20, b.generate_moves_for(Color::White).len());
     }
 
     #[test]
@@ -597,7 +997,7 @@ mod tests {
         assert_eq!(None, b[B4]);
 
         let test_move = Move::new(B2, B4, Flags::PAWN_SPRINT);
-        let _ = b.make_move(test_move.clone());
+        let _ = b.make_move(test_move);
     }
 
     #[test]
@@ -606,7 +1006,7 @@ mod tests {
             let mut b = Board::new();
             b.place(C4, Piece::new(color, Kind::Knight));
 
-            let moves = b.generate_moves(color);
+            let moves = b.generate_moves_for(color);
             assert_eq!(8, moves.len());
             assert!(moves.contains(&Move::new(C4, A3, Flags::QUIET)));
             assert!(moves.contains(&Move::new(C4, A5, Flags::QUIET)));
@@ -634,7 +1034,7 @@ mod tests {
             b.place(E3, Piece::new(color.other(), Kind::Rook));
             b.place(E5, Piece::new(color.other(), Kind::Rook));
 
-            let moves = b.generate_moves(color);
+            let moves = b.generate_moves_for(color);
             assert_eq!(8, moves.len());
             assert!(moves.contains(&Move::new(C4, A3, Flags::CAPTURE)));
             assert!(moves.contains(&Move::new(C4, A5, Flags::CAPTURE)));
@@ -656,7 +1056,7 @@ mod tests {
             b.place(B2, Piece::new(color.other(), Kind::Queen));
             b.place(B1, Piece::new(color.other(), Kind::Queen));
 
-            let moves = b.generate_moves(color);
+            let moves = b.generate_moves_for(color);
             assert_eq!(3, moves.len());
             assert!(moves.contains(&Move::new(A1, A2, Flags::CAPTURE)));
             assert!(moves.contains(&Move::new(A1, B1, Flags::CAPTURE)));
@@ -670,7 +1070,7 @@ mod tests {
             let mut b = Board::new();
             b.place(C4, Piece::new(color, Kind::Queen));
 
-            let moves = b.generate_moves(color);
+            let moves = b.generate_moves_for(color);
             assert_eq!(25, moves.len());
             assert!(moves.contains(&Move::new(C4, A2, Flags::QUIET)));
             assert!(moves.contains(&Move::new(C4, B3, Flags::QUIET)));
@@ -717,7 +1117,7 @@ mod tests {
             b.place(A2, Piece::new(color.other(), Kind::Knight));
             b.place(A6, Piece::new(color.other(), Kind::Knight));
 
-            let moves = b.generate_moves(color);
+            let moves = b.generate_moves_for(color);
             assert_eq!(25, moves.len());
             assert!(moves.contains(&Move::new(C4, A2, Flags::CAPTURE)));
             assert!(moves.contains(&Move::new(C4, B3, Flags::QUIET)));
@@ -755,7 +1155,7 @@ mod tests {
             let mut b = Board::new();
             b.place(C4, Piece::new(color, Kind::Bishop));
 
-            let moves = b.generate_moves(color);
+            let moves = b.generate_moves_for(color);
             assert_eq!(11, moves.len());
             assert!(moves.contains(&Move::new(C4, A2, Flags::QUIET)));
             assert!(moves.contains(&Move::new(C4, B3, Flags::QUIET)));
@@ -777,7 +1177,7 @@ mod tests {
             let mut b = Board::new();
             b.place(B2, Piece::new(color, Kind::Rook));
 
-            let moves = b.generate_moves(color);
+            let moves = b.generate_moves_for(color);
             assert_eq!(14, moves.len());
             assert!(moves.contains(&Move::new(B2, A2, Flags::QUIET)));
             assert!(moves.contains(&Move::new(B2, C2, Flags::QUIET)));
@@ -806,7 +1206,7 @@ mod tests {
             for color in [Color::White, Color::Black] {
                 let mut b = Board::new();
                 b.place(square, Piece::new(color, Kind::Rook));
-                assert_eq!(14, b.generate_moves(color).len());
+                assert_eq!(14, b.generate_moves_for(color).len());
             }
         }
     }
@@ -817,7 +1217,7 @@ mod tests {
             let mut b = Board::new();
             b.place(C4, Piece::new(color, Kind::King));
 
-            let moves = b.generate_moves(color);
+            let moves = b.generate_moves_for(color);
             assert_eq!(8, moves.len());
             assert!(moves.contains(&Move::new(C4, B3, Flags::QUIET)));
             assert!(moves.contains(&Move::new(C4, B4, Flags::QUIET)));
@@ -840,8 +1240,8 @@ mod tests {
             b.place(B4, Piece::new(color.other(), Kind::Pawn));
             b.place(D4, Piece::new(color.other(), Kind::Pawn));
 
-            let mut moves = Vec::new();
-            b.generate_moves_king(&mut moves, color, C4);
+            let mut moves = MoveList::new();
+            b.generate_moves_king(&mut moves, color, C4, MoveFilter::All);
             assert_eq!(6, moves.len());
             assert!(moves.contains(&Move::new(C4, B3, Flags::QUIET)));
             assert!(moves.contains(&Move::new(C4, B4, Flags::CAPTURE)));
@@ -858,8 +1258,8 @@ mod tests {
         b.place(E1, Piece::new(Color::White, Kind::King));
         b.place(H1, Piece::new(Color::White, Kind::Rook));
 
-        let mut moves = Vec::new();
-        b.generate_moves_king(&mut moves, Color::White, E1);
+        let mut moves = MoveList::new();
+        b.generate_moves_king(&mut moves, Color::White, E1, MoveFilter::All);
         assert_eq!(6, moves.len());
         assert!(moves.contains(&Move::new(E1, D1, Flags::QUIET)));
         assert!(moves.contains(&Move::new(E1, D2, Flags::QUIET)));
@@ -876,9 +1276,9 @@ mod tests {
         b.place(E1, Piece::new(Color::White, Kind::King));
         b.place(H1, Piece::new(Color::White, Kind::Rook));
 
-        let mut moves = Vec::new();
-        b.generate_moves_king(&mut moves, Color::White, E1);
-        assert_eq!(6, moves.len());
+        let mut moves = MoveList::new();
+        b.generate_moves_king(&mut moves, Color::White, E1, MoveFilter::All);
+        assert_eq!(5, moves.len());
         assert!(moves.contains(&Move::new(E1, D1, Flags::QUIET)));
         assert!(moves.contains(&Move::new(E1, D2, Flags::QUIET)));
         assert!(moves.contains(&Move::new(E1, E2, Flags::QUIET)));
@@ -892,8 +1292,8 @@ mod tests {
         b.place(E8, Piece::new(Color::Black, Kind::King));
         b.place(H8, Piece::new(Color::Black, Kind::Rook));
 
-        let mut moves = Vec::new();
-        b.generate_moves_king(&mut moves, Color::Black, E8);
+        let mut moves = MoveList::new();
+        b.generate_moves_king(&mut moves, Color::Black, E8, MoveFilter::All);
         assert_eq!(6, moves.len());
         assert!(moves.contains(&Move::new(E8, D8, Flags::QUIET)));
         assert!(moves.contains(&Move::new(E8, D7, Flags::QUIET)));
@@ -910,8 +1310,8 @@ mod tests {
         b.place(E8, Piece::new(Color::Black, Kind::King));
         b.place(H8, Piece::new(Color::Black, Kind::Rook));
 
-        let mut moves = Vec::new();
-        b.generate_moves_king(&mut moves, Color::Black, E8);
+        let mut moves = MoveList::new();
+        b.generate_moves_king(&mut moves, Color::Black, E8, MoveFilter::All);
         assert_eq!(6, moves.len());
         assert!(moves.contains(&Move::new(E8, D8, Flags::QUIET)));
         assert!(moves.contains(&Move::new(E8, D7, Flags::QUIET)));
@@ -920,12 +1320,123 @@ mod tests {
         assert!(moves.contains(&Move::new(E8, F8, Flags::QUIET)));
     }
 
+    #[test]
+    fn test_move_gen_king_castle_queenside() {
+        for color in [Color::White, Color::Black] {
+            let b = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+            let king = color.king_square();
+            let queenside = Move::new(
+                king,
+                king + Direction::Left + Direction::Left,
+                Flags::CASTLE_QUEEN,
+            );
+            let kingside = Move::new(
+                king,
+                king + Direction::Right + Direction::Right,
+                Flags::CASTLE_KING,
+            );
+
+            let moves = b.generate_moves_for(color);
+            assert!(moves.contains(&queenside));
+            assert!(moves.contains(&kingside));
+        }
+    }
+
+    #[test]
+    fn test_move_gen_king_castle_rights_separate() {
+        for (fen, kingside, queenside) in [
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", true, true),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1", true, false),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1", false, true),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1", false, false),
+        ] {
+            let b = Board::from_fen(fen).unwrap();
+            let mut moves = MoveList::new();
+            b.generate_moves_king(&mut moves, Color::White, E1, MoveFilter::All);
+
+            assert_eq!(
+                kingside,
+                moves.contains(&Move::new(E1, G1, Flags::CASTLE_KING)),
+                "{}",
+                fen
+            );
+            assert_eq!(
+                queenside,
+                moves.contains(&Move::new(E1, C1, Flags::CASTLE_QUEEN)),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_move_gen_king_castle_blocked_or_attacked() {
+        for (fen, kingside, queenside) in [
+            // the rook passes b1, so it must be empty, but may be attacked
+            ("4k3/8/8/8/8/8/8/RN2K2R w KQ - 0 1", true, false),
+            ("1r2k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", true, true),
+            // out of check
+            ("4k3/8/8/8/8/3n4/8/R3K2R w KQ - 0 1", false, false),
+            ("4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1", false, false),
+            // through check
+            ("3rk3/8/8/8/8/8/8/R3K2R w KQ - 0 1", true, false),
+            ("5rk1/8/8/8/8/8/8/R3K2R w KQ - 0 1", false, true),
+            // into check
+            ("2r1k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", true, false),
+            ("6rk/8/8/8/8/8/8/R3K2R w KQ - 0 1", false, true),
+            ("4k3/8/8/8/8/8/7p/R3K2R w KQ - 0 1", false, true),
+            ("8/8/8/8/8/8/6k1/R3K2R w KQ - 0 1", false, true),
+        ] {
+            let b = Board::from_fen(fen).unwrap();
+            let mut moves = MoveList::new();
+            b.generate_moves_king(&mut moves, Color::White, E1, MoveFilter::All);
+
+            assert_eq!(
+                kingside,
+                moves.contains(&Move::new(E1, G1, Flags::CASTLE_KING)),
+                "{}",
+                fen
+            );
+            assert_eq!(
+                queenside,
+                moves.contains(&Move::new(E1, C1, Flags::CASTLE_QUEEN)),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_square_attacked() {
+        let b = Board::from_fen("4k3/8/8/3p4/8/1n6/8/R3K2b w - - 0 1").unwrap();
+
+        // pawn
+        assert!(b.is_square_attacked(C4, Color::Black));
+        assert!(b.is_square_attacked(E4, Color::Black));
+        assert!(!b.is_square_attacked(D3, Color::Black));
+        // knight
+        assert!(b.is_square_attacked(A1, Color::Black));
+        assert!(b.is_square_attacked(D2, Color::Black));
+        // bishop, including the occupied square it is blocked by
+        assert!(b.is_square_attacked(G2, Color::Black));
+        assert!(b.is_square_attacked(D5, Color::Black));
+        assert!(!b.is_square_attacked(C6, Color::Black));
+        // king
+        assert!(b.is_square_attacked(D7, Color::Black));
+        assert!(b.is_square_attacked(F8, Color::Black));
+        // rook, blocked by its own king
+        assert!(b.is_square_attacked(D1, Color::White));
+        assert!(b.is_square_attacked(A8, Color::White));
+        assert!(!b.is_square_attacked(G1, Color::White));
+        assert!(!b.is_square_attacked(H2, Color::White));
+    }
+
     #[test]
     fn test_move_gen_pawn_simple() {
         let mut b = Board::new();
         b.place(C2, Piece::new(Color::White, Kind::Pawn));
 
-        let moves = b.generate_moves(Color::White);
+        let moves = b.generate_moves_for(Color::White);
         assert_eq!(2, moves.len());
         assert!(moves.contains(&Move::new(C2, C3, Flags::QUIET)));
         assert!(moves.contains(&Move::new(C2, C4, Flags::PAWN_SPRINT)));
@@ -936,7 +1447,7 @@ mod tests {
         let mut b = Board::new();
         b.place(C7, Piece::new(Color::White, Kind::Pawn));
 
-        let moves = b.generate_moves(Color::White);
+        let moves = b.generate_moves_for(Color::White);
         assert_eq!(4, moves.len());
         assert!(moves.contains(&Move::new(C7, C8, Flags::PROMOTION_BISHOP)));
         assert!(moves.contains(&Move::new(C7, C8, Flags::PROMOTION_KNIGHT)));
@@ -950,7 +1461,7 @@ mod tests {
         b.place(C7, Piece::new(Color::White, Kind::Pawn));
         b.place(D8, Piece::new(Color::Black, Kind::Rook));
 
-        let moves = b.generate_moves(Color::White);
+        let moves = b.generate_moves_for(Color::White);
         assert_eq!(8, moves.len());
         assert!(moves.contains(&Move::new(C7, C8, Flags::PROMOTION_BISHOP)));
         assert!(moves.contains(&Move::new(C7, C8, Flags::PROMOTION_KNIGHT)));
@@ -969,19 +1480,59 @@ mod tests {
         b.place(D5, Piece::new(Color::Black, Kind::Pawn));
         b.last_move = Some(Move::new(D7, D5, Flags::PAWN_SPRINT));
 
-        let moves = b.generate_moves(Color::White);
+        let moves = b.generate_moves_for(Color::White);
         assert_eq!(2, moves.len());
         assert!(moves.contains(&Move::new(C5, C6, Flags::QUIET)));
         assert!(moves.contains(&Move::new(C5, D6, Flags::EP_CAPTURE)));
     }
 
+    #[test]
+    fn test_make_unmake_en_passant() {
+        for (fen, mov, taken) in [
+            (
+                "4k3/8/8/2Pp4/8/8/8/4K3 w - d6 0 1",
+                Move::new(C5, D6, Flags::EP_CAPTURE),
+                D5,
+            ),
+            (
+                "4k3/8/8/8/2pP4/8/8/4K3 b - d3 0 1",
+                Move::new(C4, D3, Flags::EP_CAPTURE),
+                D4,
+            ),
+        ] {
+            let mut b = Board::from_fen(fen).unwrap();
+            let original = b.clone();
+            let pawn = b[mov.from()];
+
+            let res = b.make_move(mov);
+            assert_eq!(pawn, b[mov.to()]);
+            assert_eq!(None, b[mov.from()]);
+            assert_eq!(None, b[taken]);
+            assert_eq!(3, b.pieces().len());
+
+            b.unmake_move(res);
+            assert_eq!(original, b);
+        }
+    }
+
+    #[test]
+    fn test_en_passant_exposes_king_on_rank() {
+        let mut b = Board::from_fen("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1").unwrap();
+        let mov = Move::new(B5, C6, Flags::EP_CAPTURE);
+        assert!(b.generate_moves_for(Color::White).contains(&mov));
+
+        let res = b.make_move(mov);
+        assert!(b.king_in_check(Color::White));
+        b.unmake_move(res);
+    }
+
     #[test]
     fn test_move_gen_pawn_blocked() {
         let mut b = Board::new();
         b.place(C2, Piece::new(Color::White, Kind::Pawn));
         b.place(C3, Piece::new(Color::Black, Kind::Pawn));
 
-        let moves = b.generate_moves(Color::White);
+        let moves = b.generate_moves_for(Color::White);
         assert_eq!(0, moves.len());
     }
 
@@ -991,7 +1542,7 @@ mod tests {
         b.place(C2, Piece::new(Color::White, Kind::Pawn));
         b.place(C4, Piece::new(Color::Black, Kind::Pawn));
 
-        let moves = b.generate_moves(Color::White);
+        let moves = b.generate_moves_for(Color::White);
         assert_eq!(1, moves.len());
         assert!(moves.contains(&Move::new(C2, C3, Flags::QUIET)));
     }
@@ -1002,7 +1553,7 @@ mod tests {
         b.place(C2, Piece::new(Color::White, Kind::Pawn));
         b.place(C4, Piece::new(Color::White, Kind::Pawn));
 
-        let moves = b.generate_moves(Color::White);
+        let moves = b.generate_moves_for(Color::White);
         assert_eq!(2, moves.len());
         assert!(moves.contains(&Move::new(C2, C3, Flags::QUIET)));
         assert!(moves.contains(&Move::new(C4, C5, Flags::QUIET)));
@@ -1031,4 +1582,123 @@ mod tests {
             assert!(pieces.contains(&(i, Piece::new(Color::Black, Kind::Pawn))));
         }
     }
+
+    #[test]
+    fn test_bitboards() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+
+        assert_eq!(Bitboard::RANK_2, b.bitboard(Color::White, Kind::Pawn));
+        assert_eq!(
+            Bitboard::from_iter([E8]),
+            b.bitboard(Color::Black, Kind::King)
+        );
+        assert_eq!(
+            Bitboard::from_iter([C1, F1, C8, F8]),
+            b.kind_bitboard(Kind::Bishop)
+        );
+        assert_eq!(
+            Bitboard::RANK_7 | Bitboard::RANK_8,
+            b.color_bitboard(Color::Black)
+        );
+        assert_eq!(32, b.occupied().count());
+    }
+
+    #[test]
+    fn test_bitboards_follow_make_unmake() {
+        let mut b =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let consistent = |b: &Board| {
+            b.pieces_with_position().len() == b.occupied().count() as usize
+                && (A1..=H8).all(|s| match b[s] {
+                    None => !b.occupied().contains(s),
+                    Some(p) => b.bitboard(p.color(), p.kind()).contains(s),
+                })
+        };
+
+        for mov in b.generate_moves() {
+            let before = b.clone();
+            let modification = b.make_move(mov);
+            assert!(consistent(&b), "{}", b.to_fen());
+            b.unmake_move(modification);
+            assert_eq!(before.occupied(), b.occupied());
+            assert!(consistent(&b));
+        }
+    }
+
+    #[test]
+    fn test_generate_moves_into_reuses_list() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+
+        let mut moves = MoveList::new();
+        b.generate_moves_into(&mut moves);
+        assert_eq!(20, moves.len());
+
+        b.make_move(Move::new(E2, E4, Flags::PAWN_SPRINT));
+        b.generate_moves_into(&mut moves);
+        assert_eq!(b.generate_moves().len(), moves.len());
+        assert!(moves
+            .iter()
+            .all(|m| b[m.from()].unwrap().color() == Color::Black));
+    }
+
+    #[test]
+    fn test_is_pseudo_legal() {
+        let b =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        for mov in b.generate_moves() {
+            assert!(b.is_pseudo_legal(mov), "{:?}", mov);
+        }
+        assert!(!b.is_pseudo_legal(Move::NULL));
+        // wrong flags, a piece of the opponent and a blocked slider
+        assert!(!b.is_pseudo_legal(Move::new(E5, F7, Flags::QUIET)));
+        assert!(!b.is_pseudo_legal(Move::new(A6, B5, Flags::QUIET)));
+        assert!(!b.is_pseudo_legal(Move::new(A1, A3, Flags::QUIET)));
+        assert!(!b.is_pseudo_legal(Move::new(E1, G1, Flags::QUIET)));
+    }
+
+    #[test]
+    fn test_generate_captures() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ] {
+            let b = Board::from_fen(fen).unwrap();
+            let mut expected: Vec<Move> = b
+                .generate_moves()
+                .into_iter()
+                .filter(|m| m.is_capture())
+                .collect();
+            let mut captures = b.generate_captures().to_vec();
+            expected.sort();
+            captures.sort();
+            assert!(!captures.is_empty());
+            assert_eq!(expected, captures, "{}", fen);
+
+            // the filters split up all moves
+            let mut all = Vec::new();
+            let mut moves = MoveList::new();
+            for filter in [
+                MoveFilter::Captures,
+                MoveFilter::Promotions,
+                MoveFilter::Quiets,
+            ] {
+                b.generate_filtered_into(filter, &mut moves);
+                assert!(moves.iter().all(|m| match filter {
+                    MoveFilter::Promotions => m.is_promotion() && !m.is_capture(),
+                    MoveFilter::Quiets => !m.is_promotion() && !m.is_capture(),
+                    _ => true,
+                }));
+                all.extend(moves.iter().copied());
+            }
+            let mut expected = b.generate_moves().to_vec();
+            all.sort();
+            expected.sort();
+            assert_eq!(expected, all, "{}", fen);
+        }
+    }
 }
@@ -1,7 +1,8 @@
+use crate::chess::board::piece::{Color, Kind, Piece};
 use crate::chess::board::square::Square;
 
 use bitflags::bitflags;
-use std::fmt::{Display, Formatter};
+use std::fmt::{Debug, Display, Formatter};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Flags(u8);
@@ -25,45 +26,170 @@ bitflags! {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone)]
-pub struct Move {
-    from: Square,
-    to: Square,
-    flags: Flags,
-}
+/// A move packed into 16 bits: the origin square in bits 0-5, the target
+/// square in bits 6-11 and the [`Flags`] in bits 12-15.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Move(u16);
 
 impl Move {
+    /// A placeholder that is never a valid move, e.g. for empty slots in tables.
+    pub const NULL: Move = Move(0);
+
     pub fn new(from: Square, to: Square, flags: Flags) -> Self {
-        Self { from, to, flags }
+        Self(from as u16 | (to as u16) << 6 | (flags.bits() as u16) << 12)
+    }
+
+    /// The move from its packed form, which isn't checked for validity.
+    pub(crate) fn from_bits(bits: u16) -> Self {
+        Self(bits)
+    }
+
+    pub(crate) fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == Move::NULL
+    }
+
+    pub fn flags(&self) -> Flags {
+        Flags::from_bits_retain((self.0 >> 12) as u8)
     }
 
     pub fn is_capture(&self) -> bool {
-        self.flags.contains(Flags::CAPTURE)
+        self.flags().contains(Flags::CAPTURE)
     }
 
     pub fn is_en_passant(&self) -> bool {
-        self.flags.contains(Flags::EP_CAPTURE)
+        // promotion captures share the EP_CAPTURE bits, so this needs an exact match
+        self.flags() == Flags::EP_CAPTURE
     }
 
     pub fn is_promotion(&self) -> bool {
-        self.flags.contains(Flags::PROMOTION)
+        self.flags().contains(Flags::PROMOTION)
+    }
+
+    /// The kind of piece a pawn is promoted to, or `None` if this is not a promotion.
+    pub fn promotion_kind(&self) -> Option<Kind> {
+        if !self.is_promotion() {
+            return None;
+        }
+
+        match self.flags() & (Flags::SPECIAL1 | Flags::SPECIAL2) {
+            Flags::QUIET => Some(Kind::Knight),
+            Flags::SPECIAL2 => Some(Kind::Bishop),
+            Flags::SPECIAL1 => Some(Kind::Rook),
+            _ => Some(Kind::Queen),
+        }
+    }
+
+    pub fn is_castle(&self) -> bool {
+        self.flags() == Flags::CASTLE_KING || self.flags() == Flags::CASTLE_QUEEN
     }
 
     pub fn is_pawn_sprint(&self) -> bool {
-        self.flags == Flags::PAWN_SPRINT
+        self.flags() == Flags::PAWN_SPRINT
     }
 
     pub fn from(&self) -> Square {
-        self.from
+        Square::from((self.0 & 0x3f) as usize)
     }
 
     pub fn to(&self) -> Square {
-        self.to
+        Square::from((self.0 >> 6 & 0x3f) as usize)
+    }
+
+    /// The move in the long algebraic notation of the UCI protocol, e.g. `e2e4`,
+    /// `e7e8q` or `e1g1` for castling. [`Move::NULL`] is written as `0000`.
+    pub fn to_uci(&self) -> String {
+        if self.is_null() {
+            return "0000".to_string();
+        }
+
+        let mut uci = self.from().to_algebraic() + &self.to().to_algebraic();
+        if let Some(kind) = self.promotion_kind() {
+            uci.push(Piece::new(Color::Black, kind).to_char());
+        }
+        uci
+    }
+}
+
+impl Debug for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Move")
+            .field("from", &self.from())
+            .field("to", &self.to())
+            .field("flags", &self.flags())
+            .finish()
     }
 }
 
 impl Display for Move {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} -> {}", self.from, self.to)
+        write!(f, "{} -> {}", self.from(), self.to())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::square::Square::*;
+
+    #[test]
+    fn test_promotion_kind() {
+        for (flags, kind) in [
+            (Flags::QUIET, None),
+            (Flags::CAPTURE, None),
+            (Flags::PAWN_SPRINT, None),
+            (Flags::EP_CAPTURE, None),
+            (Flags::PROMOTION_KNIGHT, Some(Kind::Knight)),
+            (Flags::PROMOTION_BISHOP, Some(Kind::Bishop)),
+            (Flags::PROMOTION_ROOK, Some(Kind::Rook)),
+            (Flags::PROMOTION_QUEEN, Some(Kind::Queen)),
+            (Flags::CAPTURE | Flags::PROMOTION_KNIGHT, Some(Kind::Knight)),
+            (Flags::CAPTURE | Flags::PROMOTION_BISHOP, Some(Kind::Bishop)),
+            (Flags::CAPTURE | Flags::PROMOTION_ROOK, Some(Kind::Rook)),
+            (Flags::CAPTURE | Flags::PROMOTION_QUEEN, Some(Kind::Queen)),
+        ] {
+            assert_eq!(kind, Move::new(C7, C8, flags).promotion_kind());
+        }
+    }
+
+    #[test]
+    fn test_packing() {
+        for from in Square::ALL {
+            for to in [A1, H1, E4, A8, H8] {
+                for flags in [
+                    Flags::QUIET,
+                    Flags::EP_CAPTURE,
+                    Flags::CAPTURE | Flags::PROMOTION_QUEEN,
+                ] {
+                    let mov = Move::new(from, to, flags);
+                    assert_eq!(from, mov.from());
+                    assert_eq!(to, mov.to());
+                    assert_eq!(flags, mov.flags());
+                }
+            }
+        }
+        assert_eq!(2, std::mem::size_of::<Move>());
+    }
+
+    #[test]
+    fn test_null() {
+        assert!(Move::NULL.is_null());
+        assert!(!Move::new(E2, E4, Flags::PAWN_SPRINT).is_null());
+        assert_eq!(Move::NULL.from(), Move::NULL.to());
+    }
+
+    #[test]
+    fn test_to_uci() {
+        assert_eq!("e2e4", Move::new(E2, E4, Flags::PAWN_SPRINT).to_uci());
+        assert_eq!("e1g1", Move::new(E1, G1, Flags::CASTLE_KING).to_uci());
+        assert_eq!("e7e8q", Move::new(E7, E8, Flags::PROMOTION_QUEEN).to_uci());
+        assert_eq!(
+            "b2a1n",
+            Move::new(B2, A1, Flags::CAPTURE | Flags::PROMOTION_KNIGHT).to_uci()
+        );
+        assert_eq!("0000", Move::NULL.to_uci());
     }
 }
@@ -0,0 +1,186 @@
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::slice;
+
+use crate::chess::board::r#move::Move;
+
+/// No legal chess position has more than 218 moves, so this is plenty even
+/// for pseudo-legal moves.
+pub const MAX_MOVES: usize = 256;
+
+/// A list of up to [`MAX_MOVES`] moves that lives on the stack, so that move
+/// generation doesn't have to allocate.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        Self {
+            moves: [Move::NULL; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /// Appends a move.
+    ///
+    /// # Panics
+    /// If the list already holds [`MAX_MOVES`] moves.
+    pub fn push(&mut self, mov: Move) {
+        self.moves[self.len] = mov;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Keeps only the moves for which `f` returns `true`, preserving their order.
+    pub fn retain(&mut self, mut f: impl FnMut(&Move) -> bool) {
+        let mut kept = 0;
+        for i in 0..self.len {
+            if f(&self.moves[i]) {
+                self.moves[kept] = self.moves[i];
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &Self::Target {
+        &self.moves[..self.len]
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl Debug for MoveList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<Move> for MoveList {
+    fn from_iter<T: IntoIterator<Item = Move>>(iter: T) -> Self {
+        let mut list = MoveList::new();
+        for mov in iter {
+            list.push(mov);
+        }
+        list
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            list: self,
+            index: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterates over the moves of a [`MoveList`] by value.
+pub struct IntoIter {
+    list: MoveList,
+    index: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.list.len {
+            return None;
+        }
+        let mov = self.list.moves[self.index];
+        self.index += 1;
+        Some(mov)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.list.len - self.index;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::r#move::Flags;
+    use crate::chess::board::square::Direction;
+    use crate::chess::board::square::Square::*;
+
+    #[test]
+    fn test_push_and_iterate() {
+        let mut list = MoveList::new();
+        assert!(list.is_empty());
+
+        list.push(Move::new(E2, E4, Flags::PAWN_SPRINT));
+        list.push(Move::new(G1, F3, Flags::QUIET));
+
+        assert_eq!(2, list.len());
+        assert_eq!(Move::new(G1, F3, Flags::QUIET), list[1]);
+        assert!(list.contains(&Move::new(E2, E4, Flags::PAWN_SPRINT)));
+        assert_eq!(
+            vec![
+                Move::new(E2, E4, Flags::PAWN_SPRINT),
+                Move::new(G1, F3, Flags::QUIET)
+            ],
+            list.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut list: MoveList = [A2, B2, C2, D2, E2]
+            .into_iter()
+            .map(|from| Move::new(from, from + Direction::Up, Flags::QUIET))
+            .collect();
+        list.retain(|mov| mov.from() != B2 && mov.from() != E2);
+
+        let from: Vec<_> = list.iter().map(|mov| mov.from()).collect();
+        assert_eq!(vec![A2, C2, D2], from);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut list = MoveList::new();
+        for _ in 0..MAX_MOVES {
+            list.push(Move::new(A1, A2, Flags::QUIET));
+        }
+        assert_eq!(MAX_MOVES, list.len());
+
+        list.clear();
+        assert!(list.is_empty());
+    }
+}
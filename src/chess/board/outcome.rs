@@ -0,0 +1,255 @@
+use crate::chess::board::piece::{Color, Kind};
+use crate::chess::board::Board;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Outcome {
+    Checkmate {
+        winner: Color,
+    },
+    Stalemate,
+    /// Neither side can possibly checkmate the other.
+    InsufficientMaterial,
+    /// 100 halfmoves without a capture or pawn move. The game is only drawn if a player claims it.
+    FiftyMoveRule,
+    /// 150 halfmoves without a capture or pawn move, the game is drawn automatically.
+    SeventyFiveMoveRule,
+    /// The position occurred for the third time. The game is only drawn if a player claims it.
+    ThreefoldRepetition,
+    /// The position occurred for the fifth time, the game is drawn automatically.
+    FivefoldRepetition,
+}
+
+impl Outcome {
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Outcome::Checkmate { winner } => Some(*winner),
+            _ => None,
+        }
+    }
+
+    pub fn is_draw(&self) -> bool {
+        self.winner().is_none()
+    }
+
+    /// Whether the game only ends if a player claims the draw.
+    pub fn is_claimable(&self) -> bool {
+        matches!(self, Outcome::FiftyMoveRule | Outcome::ThreefoldRepetition)
+    }
+}
+
+impl Board {
+    /// Determines whether the game is over in this position, not considering
+    /// repetitions. See [`Board::outcome_with_history`] for those.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome_with_history(&[])
+    }
+
+    /// Determines whether the game is over, also considering repetitions of this
+    /// position in `history`, which holds the [`Board::zobrist_key`]s of the
+    /// positions before this one.
+    ///
+    /// Automatic endings take precedence over claimable draws, so a position that
+    /// is both a threefold repetition and a fifty-move draw is reported as the latter.
+    pub fn outcome_with_history(&self, history: &[u64]) -> Option<Outcome> {
+        if self.legal_moves().is_empty() {
+            let color = self.side_to_move;
+            return Some(if self.king_in_check(color) {
+                Outcome::Checkmate {
+                    winner: color.other(),
+                }
+            } else {
+                Outcome::Stalemate
+            });
+        }
+
+        if self.is_insufficient_material() {
+            return Some(Outcome::InsufficientMaterial);
+        }
+
+        let repetitions = 1 + history
+            .iter()
+            .filter(|&&key| key == self.zobrist_key)
+            .count();
+
+        if self.halfmove_clock >= 150 {
+            Some(Outcome::SeventyFiveMoveRule)
+        } else if repetitions >= 5 {
+            Some(Outcome::FivefoldRepetition)
+        } else if self.halfmove_clock >= 100 {
+            Some(Outcome::FiftyMoveRule)
+        } else if repetitions >= 3 {
+            Some(Outcome::ThreefoldRepetition)
+        } else {
+            None
+        }
+    }
+
+    /// Whether neither side has enough material left to ever checkmate, i.e. only
+    /// kings, a single minor piece, or bishops that all stand on the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut knights = 0;
+        let mut bishops = 0;
+        let mut bishop_square_colors = [false; 2];
+        for (square, piece) in self.pieces_with_position() {
+            match piece.kind() {
+                Kind::King => {}
+                Kind::Knight => knights += 1,
+                Kind::Bishop => {
+                    bishops += 1;
+                    bishop_square_colors[((square.rank() + square.file()) % 2) as usize] = true;
+                }
+                Kind::Pawn | Kind::Rook | Kind::Queen => return false,
+            }
+        }
+
+        // any number of bishops on squares of the same color can't mate
+        let bishops_on_one_color = !(bishop_square_colors[0] && bishop_square_colors[1]);
+        knights + bishops <= 1 || (knights == 0 && bishops_on_one_color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::r#move::{Flags, Move};
+    use crate::chess::board::setup::default_setup;
+    use crate::chess::board::square::Square::*;
+
+    #[test]
+    fn test_no_outcome() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+
+        assert_eq!(None, b.outcome());
+    }
+
+    #[test]
+    fn test_checkmate() {
+        for (fen, winner) in [
+            // fool's mate
+            (
+                "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+                Color::Black,
+            ),
+            // back rank mate
+            ("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", Color::White),
+        ] {
+            let b = Board::from_fen(fen).unwrap();
+            let outcome = b.outcome();
+            assert_eq!(Some(Outcome::Checkmate { winner }), outcome, "{}", fen);
+            assert_eq!(Some(winner), outcome.unwrap().winner());
+        }
+    }
+
+    #[test]
+    fn test_stalemate() {
+        let b = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+
+        assert_eq!(Some(Outcome::Stalemate), b.outcome());
+        assert!(b.outcome().unwrap().is_draw());
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        for (fen, insufficient) in [
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4KN2 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4KB2 w - - 0 1", true),
+            ("4kb2/8/8/8/8/8/8/4KB2 w - - 0 1", false),
+            ("4k1b1/8/8/8/8/8/8/4KB2 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/3BKB2 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1", false),
+            ("4kn2/8/8/8/8/8/8/4KB2 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/4KR2 w - - 0 1", false),
+        ] {
+            let b = Board::from_fen(fen).unwrap();
+            assert_eq!(insufficient, b.is_insufficient_material(), "{}", fen);
+            if insufficient {
+                assert_eq!(Some(Outcome::InsufficientMaterial), b.outcome(), "{}", fen);
+            }
+        }
+    }
+
+    #[test]
+    fn test_move_rules() {
+        for (clock, outcome) in [
+            (99, None),
+            (100, Some(Outcome::FiftyMoveRule)),
+            (149, Some(Outcome::FiftyMoveRule)),
+            (150, Some(Outcome::SeventyFiveMoveRule)),
+        ] {
+            let fen = format!("4k3/8/8/8/8/8/8/R3K3 w - - {} 80", clock);
+            let b = Board::from_fen(&fen).unwrap();
+            assert_eq!(outcome, b.outcome(), "{}", fen);
+        }
+
+        assert!(Outcome::FiftyMoveRule.is_claimable());
+        assert!(!Outcome::SeventyFiveMoveRule.is_claimable());
+    }
+
+    #[test]
+    fn test_checkmate_before_move_rules() {
+        let b = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 150 100").unwrap();
+
+        assert_eq!(
+            Some(Outcome::Checkmate {
+                winner: Color::White
+            }),
+            b.outcome()
+        );
+    }
+
+    #[test]
+    fn test_repetition() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+
+        let mut history = Vec::new();
+        let mut outcomes = Vec::new();
+        for _ in 0..4 {
+            for mov in [
+                Move::new(G1, F3, Flags::QUIET),
+                Move::new(G8, F6, Flags::QUIET),
+                Move::new(F3, G1, Flags::QUIET),
+                Move::new(F6, G8, Flags::QUIET),
+            ] {
+                history.push(b.zobrist_key());
+                b.make_move(mov);
+            }
+            outcomes.push(b.outcome_with_history(&history));
+        }
+
+        assert_eq!(
+            vec![
+                None,
+                Some(Outcome::ThreefoldRepetition),
+                Some(Outcome::ThreefoldRepetition),
+                Some(Outcome::FivefoldRepetition),
+            ],
+            outcomes
+        );
+    }
+
+    #[test]
+    fn test_repetition_respects_castle_rights() {
+        let mut b = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        let mut history = Vec::new();
+        for _ in 0..2 {
+            for mov in [
+                Move::new(E1, E2, Flags::QUIET),
+                Move::new(E8, E7, Flags::QUIET),
+                Move::new(E2, E1, Flags::QUIET),
+                Move::new(E7, E8, Flags::QUIET),
+            ] {
+                history.push(b.zobrist_key());
+                b.make_move(mov);
+            }
+        }
+
+        // the starting position had castle rights, so it only occurred twice now
+        assert_eq!(None, b.outcome_with_history(&history));
+    }
+}
@@ -19,6 +19,41 @@ impl Piece {
     pub fn kind(&self) -> Kind {
         self.kind
     }
+
+    /// Parses a piece from its FEN character, upper case for white and lower case for black.
+    pub fn from_char(c: char) -> Option<Self> {
+        let kind = match c.to_ascii_lowercase() {
+            'b' => Kind::Bishop,
+            'k' => Kind::King,
+            'n' => Kind::Knight,
+            'p' => Kind::Pawn,
+            'q' => Kind::Queen,
+            'r' => Kind::Rook,
+            _ => return None,
+        };
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Some(Self::new(color, kind))
+    }
+
+    /// The FEN character of this piece, upper case for white and lower case for black.
+    pub fn to_char(&self) -> char {
+        let c = match self.kind {
+            Kind::Bishop => 'b',
+            Kind::King => 'k',
+            Kind::Knight => 'n',
+            Kind::Pawn => 'p',
+            Kind::Queen => 'q',
+            Kind::Rook => 'r',
+        };
+        match self.color {
+            Color::Black => c,
+            Color::White => c.to_ascii_uppercase(),
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -31,6 +66,22 @@ pub enum Kind {
     Rook,
 }
 
+impl Kind {
+    pub const ALL: [Kind; 6] = [
+        Kind::Bishop,
+        Kind::King,
+        Kind::Knight,
+        Kind::Pawn,
+        Kind::Queen,
+        Kind::Rook,
+    ];
+
+    /// Whether a pawn can be promoted to this kind of piece.
+    pub fn is_promotion_target(&self) -> bool {
+        matches!(self, Kind::Knight | Kind::Bishop | Kind::Rook | Kind::Queen)
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum Color {
     Black,
@@ -0,0 +1,334 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::chess::board::piece::{Color, Kind, Piece};
+use crate::chess::board::r#move::{Flags, Move};
+use crate::chess::board::square::Square;
+use crate::chess::board::Board;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SanError {
+    Empty,
+    /// The move isn't written in a way that resembles SAN.
+    InvalidSyntax(String),
+    /// No legal move matches.
+    IllegalMove(String),
+    /// More than one legal move matches, e.g. `Nd2` if both knights can go there.
+    AmbiguousMove(String),
+}
+
+impl Display for SanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SanError::Empty => write!(f, "empty move"),
+            SanError::InvalidSyntax(s) => write!(f, "invalid move syntax '{}'", s),
+            SanError::IllegalMove(s) => write!(f, "illegal move '{}'", s),
+            SanError::AmbiguousMove(s) => write!(f, "ambiguous move '{}'", s),
+        }
+    }
+}
+
+impl Error for SanError {}
+
+/// The upper case letter of a piece in SAN.
+fn piece_letter(kind: Kind) -> char {
+    Piece::new(Color::White, kind).to_char()
+}
+
+impl Board {
+    /// Writes a legal move of the side to move in Standard Algebraic Notation,
+    /// e.g. `Nbd7`, `exd6`, `O-O-O` or `e8=Q#`.
+    pub fn san(&self, mov: &Move) -> String {
+        let mut san = if mov.flags() == Flags::CASTLE_KING {
+            "O-O".to_string()
+        } else if mov.flags() == Flags::CASTLE_QUEEN {
+            "O-O-O".to_string()
+        } else {
+            self.san_without_suffix(mov)
+        };
+
+        let mut b = self.clone();
+        b.make_move(*mov);
+        if b.king_in_check(b.side_to_move) {
+            san.push(if b.legal_moves().is_empty() { '#' } else { '+' });
+        }
+        san
+    }
+
+    fn san_without_suffix(&self, mov: &Move) -> String {
+        let kind = self[mov.from()].map_or(Kind::Pawn, |p| p.kind());
+        let mut san = String::new();
+
+        if kind == Kind::Pawn {
+            if mov.is_capture() {
+                san.push(mov.from().to_algebraic().remove(0));
+            }
+        } else {
+            san.push(piece_letter(kind));
+
+            // the other pieces of the same kind that can move to the same square
+            let others: Vec<Square> = self
+                .legal_moves()
+                .iter()
+                .filter(|m| m.to() == mov.to() && m.from() != mov.from())
+                .filter(|m| self[m.from()].is_some_and(|p| p.kind() == kind))
+                .map(|m| m.from())
+                .collect();
+            let from = mov.from().to_algebraic();
+            if !others.is_empty() {
+                if others.iter().all(|s| s.file() != mov.from().file()) {
+                    san.push_str(&from[0..1]);
+                } else if others.iter().all(|s| s.rank() != mov.from().rank()) {
+                    san.push_str(&from[1..2]);
+                } else {
+                    san.push_str(&from);
+                }
+            }
+        }
+
+        if mov.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&mov.to().to_algebraic());
+        if let Some(kind) = mov.promotion_kind() {
+            san.push('=');
+            san.push(piece_letter(kind));
+        }
+        san
+    }
+
+    /// Parses a move in Standard Algebraic Notation and resolves it against the
+    /// legal moves of this position.
+    ///
+    /// Common variations are accepted as well: a missing `x`, castling written
+    /// with zeros, `e8Q` instead of `e8=Q`, check and mate markers that don't
+    /// match the position, annotations like `!?` and a trailing `e.p.`.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let trimmed = san.trim();
+        let trimmed = trimmed.strip_suffix("e.p.").unwrap_or(trimmed).trim_end();
+        let trimmed = trimmed.trim_end_matches(['!', '?', '+', '#']);
+        if trimmed.is_empty() {
+            return Err(SanError::Empty);
+        }
+
+        let castle = match trimmed {
+            "O-O" | "0-0" => Some(Flags::CASTLE_KING),
+            "O-O-O" | "0-0-0" => Some(Flags::CASTLE_QUEEN),
+            _ => None,
+        };
+        let candidates: Vec<Move> = match castle {
+            Some(flags) => self
+                .legal_moves()
+                .into_iter()
+                .filter(|m| m.flags() == flags)
+                .collect(),
+            None => {
+                let pattern = SanPattern::parse(trimmed)
+                    .ok_or_else(|| SanError::InvalidSyntax(san.to_string()))?;
+                self.legal_moves()
+                    .into_iter()
+                    .filter(|m| pattern.matches(self, m))
+                    .collect()
+            }
+        };
+
+        match candidates.as_slice() {
+            [] => Err(SanError::IllegalMove(san.to_string())),
+            [mov] => Ok(*mov),
+            _ => Err(SanError::AmbiguousMove(san.to_string())),
+        }
+    }
+}
+
+/// The parts of a SAN move other than castling.
+struct SanPattern {
+    kind: Kind,
+    from_file: Option<u8>,
+    from_rank: Option<u8>,
+    to: Square,
+    promotion: Option<Kind>,
+}
+
+impl SanPattern {
+    fn parse(san: &str) -> Option<SanPattern> {
+        let mut chars: Vec<char> = san
+            .chars()
+            .filter(|c| !matches!(c, 'x' | 'X' | ':' | '-' | '='))
+            .collect();
+
+        let kind = match chars.first() {
+            Some(c) if c.is_ascii_uppercase() => {
+                let kind = Piece::from_char(*c)?.kind();
+                chars.remove(0);
+                kind
+            }
+            _ => Kind::Pawn,
+        };
+
+        let promotion = match chars.last() {
+            Some(c) if kind == Kind::Pawn && c.is_ascii_alphabetic() => {
+                let promotion = Piece::from_char(*c)?.kind();
+                if !promotion.is_promotion_target() {
+                    return None;
+                }
+                chars.pop();
+                Some(promotion)
+            }
+            _ => None,
+        };
+
+        if chars.len() < 2 {
+            return None;
+        }
+        let to: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = Square::from_algebraic(&to)?;
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+                    from_file = Some(c as u8 - b'a' + 1)
+                }
+                '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'0'),
+                _ => return None,
+            }
+        }
+
+        Some(SanPattern {
+            kind,
+            from_file,
+            from_rank,
+            to,
+            promotion,
+        })
+    }
+
+    fn matches(&self, board: &Board, mov: &Move) -> bool {
+        let from = mov.from();
+        // without a file, a pawn move can't be a capture
+        let pawn_file = (self.kind == Kind::Pawn).then(|| self.to.file());
+
+        mov.to() == self.to
+            && board[from].is_some_and(|p| p.kind() == self.kind)
+            && self
+                .from_file
+                .or(pawn_file)
+                .is_none_or(|f| f == from.file())
+            && self.from_rank.is_none_or(|r| r == from.rank())
+            && mov.promotion_kind() == self.promotion
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::setup::default_setup;
+    use crate::chess::board::square::Square::*;
+
+    #[test]
+    fn test_san_default_setup() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+
+        assert_eq!("e4", b.san(&Move::new(E2, E4, Flags::PAWN_SPRINT)));
+        assert_eq!("Nf3", b.san(&Move::new(G1, F3, Flags::QUIET)));
+    }
+
+    #[test]
+    fn test_san_disambiguation() {
+        let b = Board::from_fen("1n2k3/8/5n2/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!("Nbd7", b.san(&Move::new(B8, D7, Flags::QUIET)));
+        assert_eq!("Nfd7", b.san(&Move::new(F6, D7, Flags::QUIET)));
+
+        // the queen on h4 shares its rank with the one on e4 and its file with
+        // the one on h1, all of which can move to e1
+        let b = Board::from_fen("6k1/8/8/R7/4Q2Q/8/2K5/R6Q w - - 0 1").unwrap();
+        assert_eq!("R5a3", b.san(&Move::new(A5, A3, Flags::QUIET)));
+        assert_eq!("R1a3", b.san(&Move::new(A1, A3, Flags::QUIET)));
+        assert_eq!("Qh4e1", b.san(&Move::new(H4, E1, Flags::QUIET)));
+        assert_eq!("Qee1", b.san(&Move::new(E4, E1, Flags::QUIET)));
+        assert_eq!("Q1e1", b.san(&Move::new(H1, E1, Flags::QUIET)));
+        assert_eq!("Qb7", b.san(&Move::new(E4, B7, Flags::QUIET)));
+    }
+
+    #[test]
+    fn test_san_special_moves() {
+        let b = Board::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+
+        assert_eq!("O-O", b.san(&Move::new(E1, G1, Flags::CASTLE_KING)));
+        assert_eq!("O-O-O", b.san(&Move::new(E1, C1, Flags::CASTLE_QUEEN)));
+        assert_eq!("exd6", b.san(&Move::new(E5, D6, Flags::EP_CAPTURE)));
+        assert_eq!("b8=Q+", b.san(&Move::new(B7, B8, Flags::PROMOTION_QUEEN)));
+        assert_eq!(
+            "bxa8=N",
+            b.san(&Move::new(B7, A8, Flags::CAPTURE | Flags::PROMOTION_KNIGHT))
+        );
+        assert_eq!("Rxa8+", b.san(&Move::new(A1, A8, Flags::CAPTURE)));
+    }
+
+    #[test]
+    fn test_san_mate() {
+        let b = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!("Ra8#", b.san(&Move::new(A1, A8, Flags::QUIET)));
+    }
+
+    #[test]
+    fn test_san_roundtrip() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            let b = Board::from_fen(fen).unwrap();
+            for mov in b.legal_moves() {
+                let san = b.san(&mov);
+                assert_eq!(Ok(mov), b.parse_san(&san), "{} in {}", san, fen);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_san_lenient() {
+        let b = Board::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+
+        for (san, expected) in [
+            ("0-0", Move::new(E1, G1, Flags::CASTLE_KING)),
+            ("O-O-O!?", Move::new(E1, C1, Flags::CASTLE_QUEEN)),
+            ("exd6 e.p.", Move::new(E5, D6, Flags::EP_CAPTURE)),
+            ("ed6", Move::new(E5, D6, Flags::EP_CAPTURE)),
+            ("b8Q", Move::new(B7, B8, Flags::PROMOTION_QUEEN)),
+            ("b8=Q+#", Move::new(B7, B8, Flags::PROMOTION_QUEEN)),
+            ("Ra8", Move::new(A1, A8, Flags::CAPTURE)),
+            ("Rxa8+!", Move::new(A1, A8, Flags::CAPTURE)),
+            ("e6", Move::new(E5, E6, Flags::QUIET)),
+        ] {
+            assert_eq!(Ok(expected), b.parse_san(san), "{}", san);
+        }
+    }
+
+    #[test]
+    fn test_parse_san_errors() {
+        let b = Board::from_fen("1n2k3/8/5n2/8/8/8/1P6/4K3 b - - 0 1").unwrap();
+
+        assert_eq!(Err(SanError::Empty), b.parse_san("  "));
+        assert_eq!(
+            Err(SanError::AmbiguousMove("Nd7".to_string())),
+            b.parse_san("Nd7")
+        );
+        assert_eq!(
+            Err(SanError::IllegalMove("Nd6".to_string())),
+            b.parse_san("Nd6")
+        );
+        assert_eq!(
+            Err(SanError::InvalidSyntax("Zd5".to_string())),
+            b.parse_san("Zd5")
+        );
+        assert_eq!(
+            Err(SanError::InvalidSyntax("N".to_string())),
+            b.parse_san("N")
+        );
+        // white's pawn, but black to move
+        assert!(b.parse_san("b4").is_err());
+    }
+}
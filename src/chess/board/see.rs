@@ -0,0 +1,158 @@
+use crate::chess::board::piece::Kind;
+use crate::chess::board::r#move::Move;
+use crate::chess::board::square::Square;
+use crate::chess::board::Board;
+
+/// The value of a piece in centipawns, as used by [`Board::see`]. The king is
+/// worth more than everything else together, so that it is never traded.
+pub fn piece_value(kind: Kind) -> i32 {
+    match kind {
+        Kind::Pawn => 100,
+        Kind::Knight => 320,
+        Kind::Bishop => 330,
+        Kind::Rook => 500,
+        Kind::Queen => 900,
+        Kind::King => 20_000,
+    }
+}
+
+/// The order in which pieces recapture, least valuable first.
+const RECAPTURE_ORDER: [Kind; 6] = [
+    Kind::Pawn,
+    Kind::Knight,
+    Kind::Bishop,
+    Kind::Rook,
+    Kind::Queen,
+    Kind::King,
+];
+
+impl Board {
+    /// Static exchange evaluation: how much material the side making `mov`
+    /// wins on the target square if both sides keep capturing there with
+    /// their least valuable piece, and each side may stop when it stops
+    /// gaining. A negative value means the moved piece is lost for less.
+    ///
+    /// Pieces behind others that attack the square, like a rook behind a rook,
+    /// join in once the front piece captured. Pins are not considered.
+    pub fn see(&self, mov: &Move) -> i32 {
+        if mov.is_castle() {
+            return 0;
+        }
+        let target = mov.to();
+        let mut color = match self[mov.from()] {
+            None => return 0,
+            Some(p) => p.color(),
+        };
+
+        // gains[i] is the balance of the side making the i-th capture, if
+        // the exchange ended after it
+        let mut gains = [0; 32];
+        let mut occupied = self.occupied();
+        gains[0] = if mov.is_en_passant() {
+            piece_value(Kind::Pawn)
+        } else {
+            self[target].map_or(0, |p| piece_value(p.kind()))
+        };
+        let mut on_target = self[mov.from()].unwrap().kind();
+        if let Some(kind) = mov.promotion_kind() {
+            gains[0] += piece_value(kind) - piece_value(Kind::Pawn);
+            on_target = kind;
+        }
+        occupied.clear(mov.from());
+        if mov.is_en_passant() {
+            occupied.clear(Square::from_coordinates(mov.from().rank(), target.file()));
+        }
+
+        let mut depth = 0;
+        loop {
+            depth += 1;
+            color = color.other();
+            gains[depth] = piece_value(on_target) - gains[depth - 1];
+            if depth == gains.len() - 1 {
+                break;
+            }
+
+            let attackers = self.attackers(target, color, occupied);
+            let next = RECAPTURE_ORDER.iter().find_map(|&kind| {
+                (attackers & self.kind_bitboard(kind))
+                    .first()
+                    .map(|square| (kind, square))
+            });
+            let (kind, square) = match next {
+                None => break,
+                Some(v) => v,
+            };
+            occupied.clear(square);
+            // the king can't capture a defended piece
+            if kind == Kind::King && !self.attackers(target, color.other(), occupied).is_empty() {
+                break;
+            }
+            on_target = kind;
+        }
+
+        while depth > 1 {
+            depth -= 1;
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+        }
+        gains[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::board::Board;
+
+    fn see(fen: &str, uci: &str) -> i32 {
+        let b = Board::from_fen(fen).unwrap();
+        let mov = b.parse_uci_move(uci).unwrap();
+        b.see(&mov)
+    }
+
+    #[test]
+    fn test_undefended() {
+        assert_eq!(
+            100,
+            see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5")
+        );
+        // a quiet move loses nothing if the square isn't attacked
+        assert_eq!(0, see("4k3/8/8/8/8/8/8/4K1N1 w - - 0 1", "g1f3"));
+    }
+
+    #[test]
+    fn test_exchanges() {
+        assert_eq!(
+            -220,
+            see(
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                "d3e5"
+            )
+        );
+        // pawn takes a knight defended by a pawn
+        assert_eq!(220, see("4k3/8/2p5/3n4/4P3/8/8/4K3 w - - 0 1", "e4d5"));
+        // the rook behind the rook joins in
+        assert_eq!(100, see("4k3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"));
+        assert_eq!(-400, see("3rk3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"));
+        assert_eq!(-400, see("3rk3/3r4/8/3p4/8/8/8/3RK3 w - - 0 1", "d1d5"));
+    }
+
+    #[test]
+    fn test_hanging_pieces() {
+        // a knight moving to a square attacked by a pawn is lost
+        let fen = "4k3/8/8/8/3p4/8/8/3NK3 w - - 0 1";
+        assert_eq!(-320, see(fen, "d1e3"));
+        assert_eq!(0, see(fen, "d1f2"));
+    }
+
+    #[test]
+    fn test_special_moves() {
+        // en passant, once recaptured by the king
+        assert_eq!(0, see("8/8/8/2k5/3Pp3/8/4K3/8 b - d3 0 1", "e4d3"));
+        assert_eq!(100, see("8/8/8/8/3Pp3/8/8/k3K3 b - d3 0 1", "e4d3"));
+        // a promotion that captures wins the piece and the promotion
+        assert_eq!(1300, see("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q"));
+        // the king may capture an undefended piece, but not a defended one
+        assert_eq!(-900, see("4k3/8/8/8/8/8/3q4/4K3 b - - 0 1", "d2d1"));
+        assert_eq!(500, see("3rk3/8/8/8/8/8/3q4/3RK3 b - - 0 1", "d2d1"));
+        assert_eq!(0, see("r3k3/8/8/8/8/8/8/4K3 b q - 0 1", "e8c8"));
+    }
+}
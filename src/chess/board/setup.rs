@@ -1,6 +1,9 @@
 use crate::chess::board::piece::{Color, Kind, Piece};
 use crate::chess::board::*;
 
+/// The FEN of the position that [`default_setup`] creates.
+pub const DEFAULT_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
 pub fn default_setup(b: &mut Board) {
     // white pieces
     b.place(A1, Piece::new(Color::White, Kind::Rook));
@@ -139,6 +139,25 @@ impl Square {
     pub fn file(&self) -> u8 {
         (*self as u8 % 8) + 1
     }
+
+    /// Parses a square in algebraic notation, e.g. `e4`. Upper case files are accepted as well.
+    pub fn from_algebraic(s: &str) -> Option<Self> {
+        let mut chars = s.chars();
+        let file = chars.next()?.to_ascii_lowercase();
+        let rank = chars.next()?;
+        if chars.next().is_some() || !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+            return None;
+        }
+        Some(Square::from_coordinates(
+            rank as u8 - b'0',
+            file as u8 - b'a' + 1,
+        ))
+    }
+
+    /// Formats the square in lower case algebraic notation, e.g. `e4`.
+    pub fn to_algebraic(&self) -> String {
+        format!("{}{}", (b'a' + self.file() - 1) as char, self.rank())
+    }
 }
 
 impl Add<Direction> for Square {
@@ -157,15 +176,16 @@ impl From<Square> for usize {
 
 #[cfg(test)] // nice to have for tests, and also convenient in actual code, but really slow compared to slices
 impl std::iter::Step for Square {
-    fn steps_between(start: &Self, end: &Self) -> Option<usize> {
-        Some(*end as usize - *start as usize)
+    fn steps_between(start: &Self, end: &Self) -> (usize, Option<usize>) {
+        let steps = *end as usize - *start as usize;
+        (steps, Some(steps))
     }
 
     fn forward_checked(start: Self, count: usize) -> Option<Self> {
         if start as usize + count > Square::H8.into() {
             return None;
         }
-        Square::try_from(start as usize + count).ok()
+        Some(Square::from(start as usize + count))
     }
 
     fn backward_checked(start: Self, count: usize) -> Option<Self> {
@@ -173,7 +193,7 @@ impl std::iter::Step for Square {
             return None;
         }
 
-        Square::try_from(start as usize - count).ok()
+        Some(Square::from(start as usize - count))
     }
 }
 
@@ -211,6 +231,21 @@ mod tests {
         assert_eq!(Square::H8, Square::from_coordinates(8, 8));
     }
 
+    #[test]
+    fn test_algebraic() {
+        assert_eq!(Some(Square::A1), Square::from_algebraic("a1"));
+        assert_eq!(Some(Square::E4), Square::from_algebraic("e4"));
+        assert_eq!(Some(Square::H8), Square::from_algebraic("H8"));
+        assert_eq!(None, Square::from_algebraic("i1"));
+        assert_eq!(None, Square::from_algebraic("a9"));
+        assert_eq!(None, Square::from_algebraic("a10"));
+        assert_eq!(None, Square::from_algebraic(""));
+
+        for square in Square::ALL {
+            assert_eq!(Some(square), Square::from_algebraic(&square.to_algebraic()));
+        }
+    }
+
     #[test]
     fn test_row() {
         assert_eq!(1, Square::A1.rank());
@@ -0,0 +1,136 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::chess::board::piece::Piece;
+use crate::chess::board::r#move::Move;
+use crate::chess::board::square::Square;
+use crate::chess::board::Board;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MoveParseError {
+    /// A move consists of two squares and an optional promotion piece.
+    WrongLength(usize),
+    InvalidSquare(String),
+    InvalidPromotion(char),
+    /// The move is well-formed, but not legal in the position.
+    IllegalMove(String),
+}
+
+impl Display for MoveParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveParseError::WrongLength(n) => {
+                write!(f, "expected 4 or 5 characters, got {}", n)
+            }
+            MoveParseError::InvalidSquare(s) => write!(f, "invalid square '{}'", s),
+            MoveParseError::InvalidPromotion(c) => write!(f, "invalid promotion piece '{}'", c),
+            MoveParseError::IllegalMove(s) => write!(f, "illegal move '{}'", s),
+        }
+    }
+}
+
+impl Error for MoveParseError {}
+
+impl Board {
+    /// Parses a move in the long algebraic notation of the UCI protocol, e.g.
+    /// `e2e4` or `e7e8q`, and resolves it against the legal moves of this
+    /// position, so that the returned move carries the right [`Flags`].
+    ///
+    /// [`Flags`]: crate::chess::board::r#move::Flags
+    pub fn parse_uci_move(&self, uci: &str) -> Result<Move, MoveParseError> {
+        let chars: Vec<char> = uci.chars().collect();
+        if chars.len() != 4 && chars.len() != 5 {
+            return Err(MoveParseError::WrongLength(chars.len()));
+        }
+
+        let square = |chars: &[char]| {
+            let s: String = chars.iter().collect();
+            Square::from_algebraic(&s).ok_or(MoveParseError::InvalidSquare(s))
+        };
+        let from = square(&chars[0..2])?;
+        let to = square(&chars[2..4])?;
+        let promotion = match chars.get(4) {
+            None => None,
+            Some(&c) => match Piece::from_char(c.to_ascii_lowercase()).map(|p| p.kind()) {
+                Some(kind) if kind.is_promotion_target() => Some(kind),
+                _ => return Err(MoveParseError::InvalidPromotion(c)),
+            },
+        };
+
+        self.legal_moves()
+            .into_iter()
+            .find(|mov| mov.from() == from && mov.to() == to && mov.promotion_kind() == promotion)
+            .ok_or_else(|| MoveParseError::IllegalMove(uci.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::r#move::Flags;
+    use crate::chess::board::setup::default_setup;
+    use crate::chess::board::square::Square::*;
+
+    #[test]
+    fn test_parse_uci_move() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+
+        assert_eq!(
+            Ok(Move::new(E2, E4, Flags::PAWN_SPRINT)),
+            b.parse_uci_move("e2e4")
+        );
+        assert_eq!(
+            Ok(Move::new(G1, F3, Flags::QUIET)),
+            b.parse_uci_move("g1f3")
+        );
+        assert_eq!(
+            Err(MoveParseError::IllegalMove("e2e5".to_string())),
+            b.parse_uci_move("e2e5")
+        );
+        // black can't move
+        assert!(b.parse_uci_move("e7e5").is_err());
+    }
+
+    #[test]
+    fn test_parse_uci_move_flags() {
+        let b = Board::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+
+        for (uci, expected) in [
+            ("e1g1", Move::new(E1, G1, Flags::CASTLE_KING)),
+            ("e1c1", Move::new(E1, C1, Flags::CASTLE_QUEEN)),
+            ("e5d6", Move::new(E5, D6, Flags::EP_CAPTURE)),
+            ("a1a8", Move::new(A1, A8, Flags::CAPTURE)),
+            ("b7b8q", Move::new(B7, B8, Flags::PROMOTION_QUEEN)),
+            ("b7b8N", Move::new(B7, B8, Flags::PROMOTION_KNIGHT)),
+            (
+                "b7a8r",
+                Move::new(B7, A8, Flags::CAPTURE | Flags::PROMOTION_ROOK),
+            ),
+        ] {
+            let mov = b.parse_uci_move(uci).unwrap();
+            assert_eq!(expected, mov, "{}", uci);
+            assert_eq!(uci.to_lowercase(), mov.to_uci());
+        }
+    }
+
+    #[test]
+    fn test_parse_uci_move_errors() {
+        let b = Board::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+        assert_eq!(Err(MoveParseError::WrongLength(3)), b.parse_uci_move("e1e"));
+        assert_eq!(
+            Err(MoveParseError::InvalidSquare("i1".to_string())),
+            b.parse_uci_move("i1e2")
+        );
+        assert_eq!(
+            Err(MoveParseError::InvalidPromotion('k')),
+            b.parse_uci_move("b7b8k")
+        );
+        // a promotion needs a piece
+        assert_eq!(
+            Err(MoveParseError::IllegalMove("b7b8".to_string())),
+            b.parse_uci_move("b7b8")
+        );
+    }
+}
@@ -0,0 +1,635 @@
+//! A hand-crafted evaluation, which scores a position by summing up terms
+//! like material, piece placement and pawn structure.
+//!
+//! Every term has a midgame and an endgame value, which are blended by how
+//! much material is left on the board. [`trace`] keeps the terms apart, so
+//! that it can be shown why a position is evaluated the way it is.
+
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+use crate::chess::board::attacks;
+use crate::chess::board::bitboard::Bitboard;
+use crate::chess::board::piece::{Color, Kind};
+use crate::chess::board::square::{Direction, Square};
+use crate::chess::board::Board;
+
+mod psqt;
+
+/// The game phase with all pieces on the board. Knights and bishops count
+/// one, rooks two and queens four, pawns and kings don't count.
+pub const MAX_PHASE: i32 = 24;
+
+/// A pair of a midgame and an endgame value.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Tapered {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Tapered {
+    pub const ZERO: Tapered = Tapered { mg: 0, eg: 0 };
+
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Self { mg, eg }
+    }
+
+    /// Blends the two values, `phase` being [`MAX_PHASE`] for the midgame
+    /// and `0` for the endgame.
+    pub const fn taper(&self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+const fn s(mg: i32, eg: i32) -> Tapered {
+    Tapered::new(mg, eg)
+}
+
+impl Add for Tapered {
+    type Output = Tapered;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        s(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+
+impl AddAssign for Tapered {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Tapered {
+    type Output = Tapered;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        s(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl Neg for Tapered {
+    type Output = Tapered;
+
+    fn neg(self) -> Self::Output {
+        s(-self.mg, -self.eg)
+    }
+}
+
+impl Mul<i32> for Tapered {
+    type Output = Tapered;
+
+    fn mul(self, rhs: i32) -> Self::Output {
+        s(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+/// The parts the evaluation is made of.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Term {
+    Material,
+    PieceSquares,
+    PassedPawns,
+    IsolatedPawns,
+    DoubledPawns,
+    BackwardPawns,
+    Mobility,
+    KingSafety,
+    RookOpenFile,
+    BishopPair,
+}
+
+impl Term {
+    pub const ALL: [Term; 10] = [
+        Term::Material,
+        Term::PieceSquares,
+        Term::PassedPawns,
+        Term::IsolatedPawns,
+        Term::DoubledPawns,
+        Term::BackwardPawns,
+        Term::Mobility,
+        Term::KingSafety,
+        Term::RookOpenFile,
+        Term::BishopPair,
+    ];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Term::Material => "Material",
+            Term::PieceSquares => "Piece squares",
+            Term::PassedPawns => "Passed pawns",
+            Term::IsolatedPawns => "Isolated pawns",
+            Term::DoubledPawns => "Doubled pawns",
+            Term::BackwardPawns => "Backward pawns",
+            Term::Mobility => "Mobility",
+            Term::KingSafety => "King safety",
+            Term::RookOpenFile => "Rook on open file",
+            Term::BishopPair => "Bishop pair",
+        }
+    }
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The evaluation of a position, broken down into its terms.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct EvalTrace {
+    phase: i32,
+    terms: [[Tapered; 2]; Term::ALL.len()],
+}
+
+impl EvalTrace {
+    /// How much material is left, from [`MAX_PHASE`] in the opening down to
+    /// `0` when only pawns and kings are left.
+    pub fn phase(&self) -> i32 {
+        self.phase
+    }
+
+    /// What a term is worth for one side, without the other side's share.
+    pub fn get(&self, term: Term, color: Color) -> Tapered {
+        self.terms[term as usize][color as usize]
+    }
+
+    /// What a term contributes to the score, from white's point of view.
+    pub fn term_score(&self, term: Term) -> i32 {
+        (self.get(term, Color::White) - self.get(term, Color::Black)).taper(self.phase)
+    }
+
+    /// The tapered sum of all terms, from white's point of view. Because of
+    /// rounding, it may differ slightly from the sum of the term scores.
+    pub fn score(&self) -> i32 {
+        Term::ALL
+            .iter()
+            .fold(Tapered::ZERO, |sum, &term| {
+                sum + self.get(term, Color::White) - self.get(term, Color::Black)
+            })
+            .taper(self.phase)
+    }
+
+    fn add(&mut self, term: Term, color: Color, value: Tapered) {
+        self.terms[term as usize][color as usize] += value;
+    }
+}
+
+/// A table of all terms, with the midgame and endgame values for each side.
+impl Display for EvalTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<18} | {:>6} {:>6} | {:>6} {:>6} | {:>6}",
+            "Term", "White", "", "Black", "", "Total"
+        )?;
+        writeln!(
+            f,
+            "{:<18} | {:>6} {:>6} | {:>6} {:>6} | {:>6}",
+            "", "MG", "EG", "MG", "EG", ""
+        )?;
+        for term in Term::ALL {
+            let white = self.get(term, Color::White);
+            let black = self.get(term, Color::Black);
+            writeln!(
+                f,
+                "{:<18} | {:>6} {:>6} | {:>6} {:>6} | {:>6}",
+                term.name(),
+                white.mg,
+                white.eg,
+                black.mg,
+                black.eg,
+                self.term_score(term)
+            )?;
+        }
+        write!(
+            f,
+            "Phase {}/{}, score {} (white's point of view)",
+            self.phase,
+            MAX_PHASE,
+            self.score()
+        )
+    }
+}
+
+/// The score of the position in centipawns, from the point of view of the
+/// side to move.
+pub fn evaluate(board: &Board) -> i32 {
+    let score = trace(board).score();
+    match board.side_to_move() {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+/// Evaluates the position and keeps all terms apart.
+pub fn trace(board: &Board) -> EvalTrace {
+    let mut trace = EvalTrace {
+        phase: phase(board),
+        terms: [[Tapered::ZERO; 2]; Term::ALL.len()],
+    };
+    for color in [Color::White, Color::Black] {
+        material(board, color, &mut trace);
+        pawn_structure(board, color, &mut trace);
+        mobility(board, color, &mut trace);
+        king_safety(board, color, &mut trace);
+        rooks(board, color, &mut trace);
+        if board.bitboard(color, Kind::Bishop).count() >= 2 {
+            trace.add(Term::BishopPair, color, BISHOP_PAIR);
+        }
+    }
+    trace
+}
+
+fn phase(board: &Board) -> i32 {
+    let count = |kind| board.kind_bitboard(kind).count() as i32;
+    let phase =
+        count(Kind::Knight) + count(Kind::Bishop) + 2 * count(Kind::Rook) + 4 * count(Kind::Queen);
+    // promotions can push the phase beyond the opening
+    phase.min(MAX_PHASE)
+}
+
+const fn material_value(kind: Kind) -> Tapered {
+    match kind {
+        Kind::Pawn => s(82, 94),
+        Kind::Knight => s(337, 281),
+        Kind::Bishop => s(365, 297),
+        Kind::Rook => s(477, 512),
+        Kind::Queen => s(1025, 936),
+        Kind::King => s(0, 0),
+    }
+}
+
+fn material(board: &Board, color: Color, trace: &mut EvalTrace) {
+    for kind in Kind::ALL {
+        for square in board.bitboard(color, kind) {
+            trace.add(Term::Material, color, material_value(kind));
+            let (mg, eg) = psqt::piece_square(color, kind, square);
+            trace.add(Term::PieceSquares, color, s(mg, eg));
+        }
+    }
+}
+
+/// The bonus for a passed pawn, by the rank it is on from its own side.
+const PASSED_PAWN: [Tapered; 8] = [
+    s(0, 0),
+    s(0, 5),
+    s(5, 10),
+    s(10, 20),
+    s(20, 40),
+    s(40, 70),
+    s(60, 110),
+    s(0, 0),
+];
+const ISOLATED_PAWN: Tapered = s(-10, -15);
+const DOUBLED_PAWN: Tapered = s(-10, -20);
+const BACKWARD_PAWN: Tapered = s(-8, -10);
+
+/// The rank of a square as seen from `color`'s side of the board, from 1 to 8.
+fn relative_rank(color: Color, square: Square) -> u8 {
+    match color {
+        Color::White => square.rank(),
+        Color::Black => 9 - square.rank(),
+    }
+}
+
+/// All ranks in front of `rank`, as seen from `color`.
+fn ranks_ahead(color: Color, rank: u8) -> Bitboard {
+    match color {
+        Color::White => Bitboard(u64::MAX.checked_shl(rank as u32 * 8).unwrap_or(0)),
+        Color::Black => Bitboard((1 << ((rank - 1) * 8)) - 1),
+    }
+}
+
+fn adjacent_files(file: u8) -> Bitboard {
+    let left = if file > 1 {
+        Bitboard::file(file - 1)
+    } else {
+        Bitboard::EMPTY
+    };
+    let right = if file < 8 {
+        Bitboard::file(file + 1)
+    } else {
+        Bitboard::EMPTY
+    };
+    left | right
+}
+
+fn pawn_attacks(color: Color, pawns: Bitboard) -> Bitboard {
+    match color {
+        Color::White => pawns.shift(Direction::UpLeft) | pawns.shift(Direction::UpRight),
+        Color::Black => pawns.shift(Direction::DownLeft) | pawns.shift(Direction::DownRight),
+    }
+}
+
+fn pawn_structure(board: &Board, color: Color, trace: &mut EvalTrace) {
+    let own = board.bitboard(color, Kind::Pawn);
+    let enemy = board.bitboard(color.other(), Kind::Pawn);
+    let enemy_attacks = pawn_attacks(color.other(), enemy);
+
+    for square in own {
+        let file = Bitboard::file(square.file());
+        let neighbours = adjacent_files(square.file());
+        let ahead = ranks_ahead(color, square.rank());
+
+        if (enemy & (file | neighbours) & ahead).is_empty() {
+            let rank = relative_rank(color, square) as usize;
+            trace.add(Term::PassedPawns, color, PASSED_PAWN[rank - 1]);
+        }
+        // only the pawns behind another one are doubled
+        if !(own & file & ahead).is_empty() {
+            trace.add(Term::DoubledPawns, color, DOUBLED_PAWN);
+        }
+        if (own & neighbours).is_empty() {
+            trace.add(Term::IsolatedPawns, color, ISOLATED_PAWN);
+        } else if (own & neighbours & !ahead).is_empty() {
+            // all neighbours have advanced past it, so none can protect it
+            // when it moves up, and an enemy pawn keeps it from doing so
+            let stop = match color {
+                Color::White => Bitboard::from(square).shift(Direction::Up),
+                Color::Black => Bitboard::from(square).shift(Direction::Down),
+            };
+            if !(stop & enemy_attacks).is_empty() {
+                trace.add(Term::BackwardPawns, color, BACKWARD_PAWN);
+            }
+        }
+    }
+}
+
+/// The value of each reachable square beyond the number of squares a piece
+/// usually reaches, for knights, bishops, rooks and queens.
+const MOBILITY: [(Kind, Tapered, i32); 4] = [
+    (Kind::Knight, s(4, 4), 4),
+    (Kind::Bishop, s(5, 5), 7),
+    (Kind::Rook, s(2, 4), 7),
+    (Kind::Queen, s(1, 2), 14),
+];
+
+fn piece_attacks(kind: Kind, square: Square, occupied: Bitboard) -> Bitboard {
+    match kind {
+        Kind::Knight => attacks::knight(square),
+        Kind::Bishop => attacks::bishop(square, occupied),
+        Kind::Rook => attacks::rook(square, occupied),
+        Kind::Queen => attacks::queen(square, occupied),
+        Kind::King => attacks::king(square),
+        Kind::Pawn => unreachable!("pawn attacks depend on the color"),
+    }
+}
+
+fn mobility(board: &Board, color: Color, trace: &mut EvalTrace) {
+    // squares held by own pieces or attacked by enemy pawns don't count
+    let enemy_pawns = board.bitboard(color.other(), Kind::Pawn);
+    let area = !board.color_bitboard(color) & !pawn_attacks(color.other(), enemy_pawns);
+    let occupied = board.occupied();
+    for (kind, value, baseline) in MOBILITY {
+        for square in board.bitboard(color, kind) {
+            let reachable = (piece_attacks(kind, square, occupied) & area).count() as i32;
+            trace.add(Term::Mobility, color, value * (reachable - baseline));
+        }
+    }
+}
+
+const SHIELD_PAWN: Tapered = s(12, 0);
+const ADVANCED_SHIELD_PAWN: Tapered = s(6, 0);
+const MISSING_SHIELD_PAWN: Tapered = s(-15, 0);
+/// The weight of an attacked square around the king, by the attacker.
+const KING_ATTACK_WEIGHT: [(Kind, i32); 4] = [
+    (Kind::Knight, 2),
+    (Kind::Bishop, 2),
+    (Kind::Rook, 3),
+    (Kind::Queen, 5),
+];
+const MAX_KING_DANGER: i32 = 500;
+
+fn king_safety(board: &Board, color: Color, trace: &mut EvalTrace) {
+    let king = match board.bitboard(color, Kind::King).first() {
+        None => return,
+        Some(square) => square,
+    };
+
+    // the pawns in front of a king that stayed home
+    if relative_rank(color, king) <= 2 {
+        let pawns = board.bitboard(color, Kind::Pawn);
+        let (near, far) = match color {
+            Color::White => (Bitboard::RANK_2, Bitboard::RANK_3),
+            Color::Black => (Bitboard::RANK_7, Bitboard::RANK_6),
+        };
+        for file in king.file().max(2) - 1..=king.file().min(7) + 1 {
+            let file = Bitboard::file(file);
+            let shield = if !(pawns & file & near).is_empty() {
+                SHIELD_PAWN
+            } else if !(pawns & file & far).is_empty() {
+                ADVANCED_SHIELD_PAWN
+            } else {
+                MISSING_SHIELD_PAWN
+            };
+            trace.add(Term::KingSafety, color, shield);
+        }
+    }
+
+    // the enemy pieces eyeing the squares around the king; a single attacker
+    // is rarely dangerous, but they add up quickly
+    let zone = attacks::king(king) | Bitboard::from(king);
+    let occupied = board.occupied();
+    let mut attackers = 0;
+    let mut danger = 0;
+    for (kind, weight) in KING_ATTACK_WEIGHT {
+        for square in board.bitboard(color.other(), kind) {
+            let attacked = (piece_attacks(kind, square, occupied) & zone).count() as i32;
+            if attacked > 0 {
+                attackers += 1;
+                danger += weight * attacked;
+            }
+        }
+    }
+    if attackers >= 2 {
+        let penalty = (danger * danger / 4).min(MAX_KING_DANGER);
+        trace.add(Term::KingSafety, color, s(-penalty, 0));
+    }
+}
+
+const ROOK_OPEN_FILE: Tapered = s(25, 10);
+const ROOK_SEMI_OPEN_FILE: Tapered = s(12, 5);
+const BISHOP_PAIR: Tapered = s(30, 50);
+
+fn rooks(board: &Board, color: Color, trace: &mut EvalTrace) {
+    let own_pawns = board.bitboard(color, Kind::Pawn);
+    let pawns = board.kind_bitboard(Kind::Pawn);
+    for square in board.bitboard(color, Kind::Rook) {
+        let file = Bitboard::file(square.file());
+        if (pawns & file).is_empty() {
+            trace.add(Term::RookOpenFile, color, ROOK_OPEN_FILE);
+        } else if (own_pawns & file).is_empty() {
+            trace.add(Term::RookOpenFile, color, ROOK_SEMI_OPEN_FILE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::setup::default_setup;
+
+    fn trace_fen(fen: &str) -> EvalTrace {
+        trace(&Board::from_fen(fen).unwrap())
+    }
+
+    /// The same position with the colors swapped and the board flipped.
+    fn mirror(fen: &str) -> String {
+        let fields = fen.split(' ').collect::<Vec<_>>();
+        let swap_case = |s: &str| {
+            s.chars()
+                .map(|c| {
+                    if c.is_ascii_uppercase() {
+                        c.to_ascii_lowercase()
+                    } else {
+                        c.to_ascii_uppercase()
+                    }
+                })
+                .collect::<String>()
+        };
+        let ranks = fields[0].split('/').rev().collect::<Vec<_>>().join("/");
+        let side = if fields[1] == "w" { "b" } else { "w" };
+        format!(
+            "{} {} {} - 0 1",
+            swap_case(&ranks),
+            side,
+            swap_case(fields[2])
+        )
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let mut board = Board::new();
+        board.populate(default_setup);
+        let trace = trace(&board);
+        assert_eq!(MAX_PHASE, trace.phase());
+        assert_eq!(0, trace.score());
+        assert_eq!(0, evaluate(&board));
+        assert_eq!(
+            s(
+                8 * 82 + 2 * (337 + 365 + 477) + 1025,
+                8 * 94 + 2 * (281 + 297 + 512) + 936
+            ),
+            trace.get(Term::Material, Color::White)
+        );
+    }
+
+    #[test]
+    fn test_symmetry() {
+        for fen in [
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "6k1/5ppp/8/6NQ/8/8/5PPP/6K1 b - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let mirrored = Board::from_fen(&mirror(fen)).unwrap();
+            assert_eq!(evaluate(&board), evaluate(&mirrored), "{}", fen);
+            assert_eq!(trace(&board).score(), -trace(&mirrored).score(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_phase() {
+        assert_eq!(
+            0,
+            trace_fen("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1").phase()
+        );
+        assert_eq!(7, trace_fen("3qk3/8/8/8/8/8/8/1N1RK3 w - - 0 1").phase());
+        // the endgame value counts fully without pieces
+        let trace = trace_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        assert_eq!(
+            trace.get(Term::Material, Color::White).eg,
+            trace.term_score(Term::Material)
+        );
+    }
+
+    #[test]
+    fn test_side_to_move() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3";
+        let white = Board::from_fen(&format!("{} w - - 0 1", fen)).unwrap();
+        let black = Board::from_fen(&format!("{} b - - 0 1", fen)).unwrap();
+        assert!(evaluate(&white) > 0);
+        assert_eq!(evaluate(&white), -evaluate(&black));
+    }
+
+    #[test]
+    fn test_pawn_structure() {
+        let trace = trace_fen("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1");
+        assert_eq!(PASSED_PAWN[4], trace.get(Term::PassedPawns, Color::White));
+        // a pawn on the next file stops it from being passed
+        let trace = trace_fen("4k3/4p3/8/3P4/8/8/8/4K3 w - - 0 1");
+        assert_eq!(Tapered::ZERO, trace.get(Term::PassedPawns, Color::White));
+
+        let trace = trace_fen("4k3/8/8/8/8/4P3/P3P3/4K3 w - - 0 1");
+        assert_eq!(
+            ISOLATED_PAWN * 3,
+            trace.get(Term::IsolatedPawns, Color::White)
+        );
+        assert_eq!(DOUBLED_PAWN, trace.get(Term::DoubledPawns, Color::White));
+
+        // e3 can't advance without being taken by f5, and d4 can't protect it
+        let trace = trace_fen("4k3/8/8/5p2/3P4/4P3/8/4K3 w - - 0 1");
+        assert_eq!(BACKWARD_PAWN, trace.get(Term::BackwardPawns, Color::White));
+        assert_eq!(Tapered::ZERO, trace.get(Term::BackwardPawns, Color::Black));
+        assert_eq!(ISOLATED_PAWN, trace.get(Term::IsolatedPawns, Color::Black));
+    }
+
+    #[test]
+    fn test_mobility() {
+        let center = trace_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+        let corner = trace_fen("4k3/8/8/8/8/8/8/N3K3 w - - 0 1");
+        assert_eq!(s(16, 16), center.get(Term::Mobility, Color::White));
+        assert_eq!(s(-8, -8), corner.get(Term::Mobility, Color::White));
+        // squares attacked by enemy pawns don't count
+        let trace = trace_fen("4k3/8/2p1p3/8/3N4/8/8/4K3 w - - 0 1");
+        assert_eq!(s(8, 8), trace.get(Term::Mobility, Color::White));
+    }
+
+    #[test]
+    fn test_king_safety() {
+        let safe = trace_fen("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1");
+        assert_eq!(SHIELD_PAWN * 3, safe.get(Term::KingSafety, Color::Black));
+        let exposed = trace_fen("6k1/8/8/8/8/8/5PPP/6K1 w - - 0 1");
+        assert_eq!(
+            MISSING_SHIELD_PAWN * 3,
+            exposed.get(Term::KingSafety, Color::Black)
+        );
+
+        // the queen and the knight both eye f7 and h7
+        let attacked = trace_fen("6k1/5ppp/8/6NQ/8/8/5PPP/6K1 w - - 0 1");
+        assert!(
+            attacked.get(Term::KingSafety, Color::Black).mg
+                < safe.get(Term::KingSafety, Color::Black).mg
+        );
+        assert_eq!(
+            safe.get(Term::KingSafety, Color::White),
+            attacked.get(Term::KingSafety, Color::White)
+        );
+    }
+
+    #[test]
+    fn test_rooks_and_bishops() {
+        let trace = trace_fen("r3k3/p7/8/8/8/8/1P6/R2RK3 w - - 0 1");
+        assert_eq!(
+            ROOK_OPEN_FILE + ROOK_SEMI_OPEN_FILE,
+            trace.get(Term::RookOpenFile, Color::White)
+        );
+        assert_eq!(Tapered::ZERO, trace.get(Term::RookOpenFile, Color::Black));
+
+        let trace = trace_fen("2b1kb2/8/8/8/8/8/8/2B1K3 w - - 0 1");
+        assert_eq!(Tapered::ZERO, trace.get(Term::BishopPair, Color::White));
+        assert_eq!(BISHOP_PAIR, trace.get(Term::BishopPair, Color::Black));
+        assert!(trace.term_score(Term::BishopPair) < 0);
+    }
+
+    #[test]
+    fn test_display() {
+        let trace = trace_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let table = trace.to_string();
+        for term in Term::ALL {
+            assert!(table.contains(term.name()), "{}", table);
+        }
+        assert!(table.ends_with(&format!("score {} (white's point of view)", trace.score())));
+    }
+}
@@ -1,2 +1,7 @@
 pub mod board;
+pub mod eval;
+pub mod nnue;
 pub mod perft;
+pub mod pgn;
+pub mod search;
+pub mod uci;
@@ -0,0 +1,469 @@
+//! An efficiently updatable neural network (NNUE), which can replace the
+//! hand-crafted evaluation.
+//!
+//! The network has 768 inputs, one for each piece of either color on each
+//! square, a hidden layer and a single output. The hidden layer is computed
+//! twice, once from each side's point of view, and kept in an [`Accumulator`]
+//! that the board updates whenever a piece is placed or removed, so that a
+//! move costs a few vector additions instead of a full recomputation.
+//!
+//! Networks are stored little-endian in this format:
+//!
+//! | Field           | Type                              |
+//! |-----------------|-----------------------------------|
+//! | magic           | `b"LCNN"`                         |
+//! | version         | `u32`, currently 1                |
+//! | hidden size `H` | `u32`, a multiple of 16           |
+//! | feature weights | `768 × H` `i16`, by input         |
+//! | feature biases  | `H` `i16`                         |
+//! | output weights  | `2 × H` `i16`, side to move first |
+//! | output bias     | `i32`                             |
+//!
+//! The hidden layer is clipped to `0..=QA`, and the output is scaled to
+//! centipawns by `SCALE / (QA × QB)`.
+
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+use std::{fs, io};
+
+use crate::chess::board::piece::{Color, Kind, Piece};
+use crate::chess::board::square::Square;
+use crate::chess::board::Board;
+
+mod simd;
+
+pub const INPUTS: usize = 768;
+/// The hidden layer is clipped to this value, the scale of the feature weights.
+pub const QA: i32 = 255;
+/// The scale of the output weights.
+pub const QB: i32 = 64;
+/// Turns the output of the network into centipawns.
+pub const SCALE: i32 = 400;
+
+const MAGIC: &[u8; 4] = b"LCNN";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 12;
+/// The hidden layer is processed in chunks of this many values.
+const HIDDEN_ALIGNMENT: usize = 16;
+
+#[derive(Debug)]
+pub enum NetworkError {
+    Io(io::Error),
+    /// The data doesn't start with the magic bytes of a network.
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    /// The hidden layer is empty or not a multiple of 16.
+    InvalidHiddenSize(usize),
+    /// The data doesn't have the length the header announces.
+    WrongLength {
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkError::Io(e) => write!(f, "{}", e),
+            NetworkError::InvalidMagic => write!(f, "not a network file"),
+            NetworkError::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            NetworkError::InvalidHiddenSize(n) => {
+                write!(f, "hidden size {} is not a positive multiple of 16", n)
+            }
+            NetworkError::WrongLength { expected, actual } => {
+                write!(f, "expected {} bytes, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl Error for NetworkError {}
+
+impl From<io::Error> for NetworkError {
+    fn from(e: io::Error) -> Self {
+        NetworkError::Io(e)
+    }
+}
+
+/// The weights of a network, see the [module documentation](self).
+#[derive(Clone, Eq, PartialEq)]
+pub struct Network {
+    hidden: usize,
+    feature_weights: Box<[i16]>,
+    feature_biases: Box<[i16]>,
+    output_weights: Box<[i16]>,
+    output_bias: i32,
+}
+
+impl Network {
+    pub fn load(path: impl AsRef<Path>) -> Result<Network, NetworkError> {
+        Network::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, NetworkError> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+            return Err(NetworkError::InvalidMagic);
+        }
+        let mut reader = Reader(&bytes[4..]);
+        let version = reader.u32();
+        if version != VERSION {
+            return Err(NetworkError::UnsupportedVersion(version));
+        }
+        let hidden = reader.u32() as usize;
+        if hidden == 0 || !hidden.is_multiple_of(HIDDEN_ALIGNMENT) {
+            return Err(NetworkError::InvalidHiddenSize(hidden));
+        }
+        let expected = HEADER_LEN + (INPUTS * hidden + 3 * hidden) * 2 + 4;
+        if bytes.len() != expected {
+            return Err(NetworkError::WrongLength {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+
+        Ok(Network {
+            hidden,
+            feature_weights: reader.i16s(INPUTS * hidden),
+            feature_biases: reader.i16s(hidden),
+            output_weights: reader.i16s(2 * hidden),
+            output_bias: reader.u32() as i32,
+        })
+    }
+
+    /// The network in the format [`Network::from_bytes`] reads.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + (INPUTS + 3) * self.hidden * 2 + 4);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.hidden as u32).to_le_bytes());
+        for values in [
+            &self.feature_weights,
+            &self.feature_biases,
+            &self.output_weights,
+        ] {
+            values
+                .iter()
+                .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()));
+        }
+        bytes.extend_from_slice(&self.output_bias.to_le_bytes());
+        bytes
+    }
+
+    /// The number of neurons in the hidden layer.
+    pub fn hidden_size(&self) -> usize {
+        self.hidden
+    }
+
+    fn feature_weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+}
+
+/// Printing all weights isn't helpful.
+impl Debug for Network {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Network")
+            .field("hidden", &self.hidden)
+            .finish_non_exhaustive()
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let (value, rest) = self.0.split_first_chunk::<N>().unwrap();
+        self.0 = rest;
+        *value
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take())
+    }
+
+    fn i16s(&mut self, len: usize) -> Box<[i16]> {
+        (0..len).map(|_| i16::from_le_bytes(self.take())).collect()
+    }
+}
+
+/// The input of a piece on a square, from `perspective`'s point of view. Each
+/// side sees its own pieces first and the board flipped for black, so that
+/// both sides look the same to the network.
+fn feature(perspective: Color, piece: Piece, square: Square) -> usize {
+    let square = match perspective {
+        Color::White => square as usize,
+        Color::Black => square as usize ^ 56,
+    };
+    let kind = match piece.kind() {
+        Kind::Pawn => 0,
+        Kind::Knight => 1,
+        Kind::Bishop => 2,
+        Kind::Rook => 3,
+        Kind::Queen => 4,
+        Kind::King => 5,
+    };
+    let side = if piece.color() == perspective { 0 } else { 6 };
+    (side + kind) * 64 + square
+}
+
+/// The hidden layer of a network for a position, from both sides' point of
+/// view. A [`Board`] with a network keeps it up to date, see
+/// [`Board::set_network`].
+#[derive(Clone)]
+pub struct Accumulator {
+    network: Arc<Network>,
+    /// Indexed by the color whose point of view it is.
+    values: [Box<[i16]>; 2],
+}
+
+impl Accumulator {
+    /// Computes the hidden layer for the position from scratch.
+    pub fn new(network: Arc<Network>, board: &Board) -> Self {
+        let mut accumulator = Self {
+            values: [
+                network.feature_biases.clone(),
+                network.feature_biases.clone(),
+            ],
+            network,
+        };
+        for square in Square::ALL {
+            if let Some(piece) = board[square] {
+                accumulator.add(piece, square);
+            }
+        }
+        accumulator
+    }
+
+    pub fn network(&self) -> &Arc<Network> {
+        &self.network
+    }
+
+    pub(crate) fn add(&mut self, piece: Piece, square: Square) {
+        self.update(piece, square, i16::wrapping_add);
+    }
+
+    pub(crate) fn remove(&mut self, piece: Piece, square: Square) {
+        self.update(piece, square, i16::wrapping_sub);
+    }
+
+    // wrapping, so that removing a piece always restores the values from
+    // before it was added
+    fn update(&mut self, piece: Piece, square: Square, op: fn(i16, i16) -> i16) {
+        for perspective in [Color::Black, Color::White] {
+            let weights = self
+                .network
+                .feature_weights(feature(perspective, piece, square));
+            let values = &mut self.values[perspective as usize];
+            for (value, &weight) in values.iter_mut().zip(weights) {
+                *value = op(*value, weight);
+            }
+        }
+    }
+
+    /// The output of the network in centipawns, from the point of view of the
+    /// side to move.
+    pub fn evaluate(&self, side_to_move: Color) -> i32 {
+        let hidden = self.network.hidden;
+        let weights = &self.network.output_weights;
+        let us = simd::crelu_dot(&self.values[side_to_move as usize], &weights[..hidden]);
+        let them = simd::crelu_dot(
+            &self.values[side_to_move.other() as usize],
+            &weights[hidden..],
+        );
+        let output = us as i64 + them as i64 + self.network.output_bias as i64;
+        (output * SCALE as i64 / (QA * QB) as i64) as i32
+    }
+}
+
+/// Accumulators of the same network are equal if their values are.
+impl PartialEq for Accumulator {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.network, &other.network) && self.values == other.values
+    }
+}
+
+impl Eq for Accumulator {}
+
+impl Debug for Accumulator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Accumulator")
+            .field("network", &self.network)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The network's evaluation of the position if the board has a network, from
+/// the point of view of the side to move.
+pub fn evaluate(board: &Board) -> Option<i32> {
+    board
+        .accumulator()
+        .map(|accumulator| accumulator.evaluate(board.side_to_move()))
+}
+
+/// A small network in the repository that counts material and pawn advances,
+/// so that its output can be checked by hand.
+#[cfg(test)]
+pub(crate) const FIXTURE_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/src/chess/nnue/fixture.nnue");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::move_list::MoveList;
+    use crate::chess::board::setup::default_setup;
+
+    const HIDDEN: usize = 16;
+
+    /// The network stored at [`FIXTURE_PATH`]. The first neuron sums up the
+    /// material of the own pieces, two per pawn; the second counts how far
+    /// the own pawns advanced; the third is a bonus for the side to move.
+    fn fixture() -> Network {
+        let mut feature_weights = vec![0; INPUTS * HIDDEN];
+        for (kind, value) in [2, 6, 6, 10, 18, 0].into_iter().enumerate() {
+            for square in 0..64 {
+                feature_weights[(kind * 64 + square) * HIDDEN] = value;
+            }
+        }
+        for square in 8..56 {
+            feature_weights[square * HIDDEN + 1] = (square / 8) as i16 - 1;
+        }
+        let mut feature_biases = vec![0; HIDDEN];
+        feature_biases[2] = 16;
+        let mut output_weights = vec![0; 2 * HIDDEN];
+        // a pawn is worth 100 centipawns, an advance 10, the move 20
+        output_weights[0] = 2040;
+        output_weights[HIDDEN] = -2040;
+        output_weights[1] = 408;
+        output_weights[HIDDEN + 1] = -408;
+        output_weights[2] = 51;
+        Network {
+            hidden: HIDDEN,
+            feature_weights: feature_weights.into(),
+            feature_biases: feature_biases.into(),
+            output_weights: output_weights.into(),
+            output_bias: 0,
+        }
+    }
+
+    fn evaluate_fen(network: &Arc<Network>, fen: &str) -> i32 {
+        let mut board = Board::from_fen(fen).unwrap();
+        board.set_network(Some(Arc::clone(network)));
+        evaluate(&board).unwrap()
+    }
+
+    #[test]
+    fn test_load_fixture() {
+        let network = Network::load(FIXTURE_PATH).unwrap();
+        assert_eq!(fixture(), network);
+        assert_eq!(HIDDEN, network.hidden_size());
+        assert_eq!(fs::read(FIXTURE_PATH).unwrap(), network.to_bytes());
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let bytes = fixture().to_bytes();
+        assert!(matches!(
+            Network::load("does/not/exist.nnue"),
+            Err(NetworkError::Io(_))
+        ));
+        assert!(matches!(
+            Network::from_bytes(b"NNUE and more"),
+            Err(NetworkError::InvalidMagic)
+        ));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 2;
+        assert!(matches!(
+            Network::from_bytes(&wrong_version),
+            Err(NetworkError::UnsupportedVersion(2))
+        ));
+        let mut wrong_hidden = bytes.clone();
+        wrong_hidden[8] = 10;
+        assert!(matches!(
+            Network::from_bytes(&wrong_hidden),
+            Err(NetworkError::InvalidHiddenSize(10))
+        ));
+        assert!(matches!(
+            Network::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(NetworkError::WrongLength { expected, actual }) if actual == expected - 1
+        ));
+    }
+
+    #[test]
+    fn test_output() {
+        let network = Arc::new(fixture());
+        let mut board = Board::new();
+        board.populate(default_setup);
+        board.set_network(Some(Arc::clone(&network)));
+        // only the bonus for the move
+        assert_eq!(Some(20), evaluate(&board));
+
+        // e4 advanced two ranks
+        board.make_move(board.parse_uci_move("e2e4").unwrap());
+        assert_eq!(Some(20 - 20), evaluate(&board));
+
+        let fen = "4k3/8/8/8/8/8/3P4/3QK3";
+        assert_eq!(
+            9 * 100 + 100 + 20,
+            evaluate_fen(&network, &format!("{} w - - 0 1", fen))
+        );
+        assert_eq!(
+            -(9 * 100 + 100) + 20,
+            evaluate_fen(&network, &format!("{} b - - 0 1", fen))
+        );
+        // both sides see the same in mirrored positions
+        assert_eq!(
+            evaluate_fen(&network, "4k3/8/8/1p6/8/8/8/4K3 w - - 0 1"),
+            evaluate_fen(&network, "4k3/8/8/8/1P6/8/8/4K3 b - - 0 1")
+        );
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        let network = Arc::new(fixture());
+        // the position has castling, en passant and promotions
+        let mut board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        board.set_network(Some(Arc::clone(&network)));
+        let start = board.clone();
+
+        fn walk(board: &mut Board, network: &Arc<Network>, depth: usize) {
+            let expected = Accumulator::new(Arc::clone(network), board);
+            assert_eq!(Some(&expected), board.accumulator());
+            if depth == 0 {
+                return;
+            }
+            let mut moves = MoveList::new();
+            board.legal_moves_into(&mut moves);
+            for mov in moves.iter() {
+                let modification = board.make_move(*mov);
+                walk(board, network, depth - 1);
+                board.unmake_move(modification);
+            }
+        }
+        walk(&mut board, &network, 2);
+        assert_eq!(start, board);
+        assert_eq!(start.accumulator(), board.accumulator());
+
+        let mut board = Board::from_fen("4k3/1P6/8/2pP4/8/8/8/4K3 w - c6 0 1").unwrap();
+        board.set_network(Some(Arc::clone(&network)));
+        walk(&mut board, &network, 3);
+    }
+
+    #[test]
+    fn test_without_network() {
+        let mut board = Board::new();
+        board.populate(default_setup);
+        assert_eq!(None, evaluate(&board));
+
+        // the network doesn't take part in comparing positions
+        let without_network = board.clone();
+        board.set_network(Some(Arc::new(fixture())));
+        assert_eq!(without_network, board);
+        board.set_network(None);
+        assert_eq!(None, board.accumulator());
+    }
+}
@@ -0,0 +1,115 @@
+//! The dot product of the clipped accumulator with the output weights, which
+//! is where the forward pass spends its time.
+//!
+//! Like the magic bitboards, the implementation is picked when compiling: AVX2
+//! if the target supports it, SSE2 on any other x86-64, and plain Rust
+//! elsewhere. All of them wrap on overflow, so they give the same results.
+
+use super::QA;
+
+/// The sum of `clamp(input[i], 0, QA) * weights[i]`. Both slices must have the
+/// same length, which must be a multiple of [`super::HIDDEN_ALIGNMENT`].
+#[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+pub(super) fn crelu_dot(input: &[i16], weights: &[i16]) -> i32 {
+    use std::arch::x86_64::*;
+
+    debug_assert_eq!(input.len(), weights.len());
+    // SAFETY: this is only compiled if the target supports AVX2, and every
+    // load reads a chunk of 16 values that lies within its slice
+    unsafe {
+        let zero = _mm256_setzero_si256();
+        let max = _mm256_set1_epi16(QA as i16);
+        let mut sum = _mm256_setzero_si256();
+        for (x, w) in input.chunks_exact(16).zip(weights.chunks_exact(16)) {
+            let x = _mm256_loadu_si256(x.as_ptr().cast());
+            let w = _mm256_loadu_si256(w.as_ptr().cast());
+            let x = _mm256_min_epi16(_mm256_max_epi16(x, zero), max);
+            // multiplies the 16-bit lanes and adds neighbouring products
+            sum = _mm256_add_epi32(sum, _mm256_madd_epi16(x, w));
+        }
+        let low = _mm256_castsi256_si128(sum);
+        let high = _mm256_extracti128_si256::<1>(sum);
+        horizontal_sum(_mm_add_epi32(low, high))
+    }
+}
+
+/// See the AVX2 variant. SSE2 is part of every x86-64 CPU.
+#[cfg(all(target_arch = "x86_64", not(target_feature = "avx2")))]
+pub(super) fn crelu_dot(input: &[i16], weights: &[i16]) -> i32 {
+    use std::arch::x86_64::*;
+
+    debug_assert_eq!(input.len(), weights.len());
+    // SAFETY: SSE2 is always available on x86-64, and every load reads a
+    // chunk of 8 values that lies within its slice
+    unsafe {
+        let zero = _mm_setzero_si128();
+        let max = _mm_set1_epi16(QA as i16);
+        let mut sum = _mm_setzero_si128();
+        for (x, w) in input.chunks_exact(8).zip(weights.chunks_exact(8)) {
+            let x = _mm_loadu_si128(x.as_ptr().cast());
+            let w = _mm_loadu_si128(w.as_ptr().cast());
+            let x = _mm_min_epi16(_mm_max_epi16(x, zero), max);
+            sum = _mm_add_epi32(sum, _mm_madd_epi16(x, w));
+        }
+        horizontal_sum(sum)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn horizontal_sum(sum: std::arch::x86_64::__m128i) -> i32 {
+    use std::arch::x86_64::*;
+
+    // SAFETY: SSE2 is always available on x86-64
+    unsafe {
+        let sum = _mm_add_epi32(sum, _mm_shuffle_epi32::<0b01_00_11_10>(sum));
+        let sum = _mm_add_epi32(sum, _mm_shuffle_epi32::<0b10_11_00_01>(sum));
+        _mm_cvtsi128_si32(sum)
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(super) fn crelu_dot(input: &[i16], weights: &[i16]) -> i32 {
+    crelu_dot_scalar(input, weights)
+}
+
+#[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+pub(super) fn crelu_dot_scalar(input: &[i16], weights: &[i16]) -> i32 {
+    input.iter().zip(weights).fold(0i32, |sum, (&x, &w)| {
+        let x = x.clamp(0, QA as i16) as i32;
+        sum.wrapping_add(x * w as i32)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_scalar() {
+        // a simple generator, so that the test is the same on every run
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as i16
+        };
+        for len in [16, 32, 256] {
+            let input = (0..len).map(|_| next() / 64).collect::<Vec<_>>();
+            let weights = (0..len).map(|_| next() / 8).collect::<Vec<_>>();
+            assert_eq!(
+                crelu_dot_scalar(&input, &weights),
+                crelu_dot(&input, &weights)
+            );
+        }
+
+        // values beyond the clipping range on both sides
+        let input = [-1000, -1, 0, 1, 254, 255, 256, i16::MAX].repeat(2);
+        let weights = [3; 16];
+        assert_eq!(3 * 2 * (1 + 254 + 255 * 3), crelu_dot(&input, &weights));
+        assert_eq!(
+            3 * 2 * (1 + 254 + 255 * 3),
+            crelu_dot_scalar(&input, &weights)
+        );
+    }
+}
@@ -1,23 +1,87 @@
-use crate::chess::board::piece::Color;
 use crate::chess::board::Board;
+use crate::chess::search::tt::{TableEntry, TranspositionTable};
 
-pub fn perft(depth: usize, board: &mut Board, color: Color) -> usize {
+pub fn perft(depth: usize, board: &mut Board) -> usize {
     if depth == 0 {
         return 1;
     }
 
     let mut result: usize = 0;
 
-    let moves = board.generate_moves(color);
+    let moves = board.legal_moves();
     for mov in moves {
-        let modification = board.make_move(mov.clone());
-        if !board.king_in_check(color) {
-            result += perft(depth - 1, board, color.other());
+        let modification = board.make_move(mov);
+        result += perft(depth - 1, board);
+        board.unmake_move(modification);
+    }
+
+    result
+}
+
+/// The number of leaf nodes below a position, as stored by [`perft_hashed`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PerftEntry {
+    pub depth: u8,
+    /// At most 48 bits, larger counts aren't stored.
+    pub nodes: u64,
+}
+
+const MAX_STORED_NODES: u64 = (1 << 48) - 1;
+
+impl TableEntry for PerftEntry {
+    fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    fn pack(&self) -> u64 {
+        self.depth as u64 | self.nodes << 8
+    }
+
+    fn unpack(bits: u64) -> Self {
+        Self {
+            depth: bits as u8,
+            nodes: bits >> 8,
         }
+    }
+}
 
+/// Like [`perft`], but remembers the counts of positions in `table`, so that
+/// positions reached by different move orders are only counted once.
+pub fn perft_hashed(
+    depth: usize,
+    board: &mut Board,
+    table: &TranspositionTable<PerftEntry>,
+) -> usize {
+    if depth == 0 {
+        return 1;
+    }
+    if depth == 1 {
+        return board.legal_moves().len();
+    }
+
+    let key = board.zobrist_key();
+    if let Some(entry) = table.probe(key) {
+        if entry.depth as usize == depth {
+            return entry.nodes as usize;
+        }
+    }
+
+    let mut result: usize = 0;
+
+    let moves = board.legal_moves();
+    for mov in moves {
+        let modification = board.make_move(mov);
+        result += perft_hashed(depth - 1, board, table);
         board.unmake_move(modification);
     }
 
+    if depth <= u8::MAX as usize && result as u64 <= MAX_STORED_NODES {
+        let entry = PerftEntry {
+            depth: depth as u8,
+            nodes: result as u64,
+        };
+        table.store(key, entry);
+    }
     result
 }
 
@@ -41,11 +105,137 @@ mod tests {
 
             assert_eq!(
                 table.1,
-                perft(table.0, &mut b, Color::White),
+                perft(table.0, &mut b),
                 "assert perft({}) == {}",
                 table.0,
                 table.1
             );
         }
     }
+
+    fn assert_perft_table(fen: &str, table: &[(usize, usize)]) {
+        let hash_table = TranspositionTable::new(1);
+        for &(depth, expected) in table {
+            let mut b = Board::from_fen(fen).unwrap();
+            assert_eq!(
+                expected,
+                perft_hashed(depth, &mut b, &hash_table),
+                "assert perft_hashed({}) == {} for {}",
+                depth,
+                expected,
+                fen
+            );
+
+            let mut b = Board::from_fen(fen).unwrap();
+
+            assert_eq!(
+                expected,
+                perft(depth, &mut b),
+                "assert perft({}) == {} for {}",
+                depth,
+                expected,
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_perft_hashed() {
+        let mut b = Board::new();
+        b.populate(default_setup);
+
+        // a table with a single slot constantly replaces its entry
+        for megabytes in [0, 1] {
+            let table = TranspositionTable::new(megabytes);
+            assert_eq!(197281, perft_hashed(4, &mut b, &table));
+            assert_eq!(197281, perft_hashed(4, &mut b, &table));
+            assert_eq!(8902, perft_hashed(3, &mut b, &table));
+        }
+
+        let entry = PerftEntry {
+            depth: 7,
+            nodes: MAX_STORED_NODES,
+        };
+        assert_eq!(entry, PerftEntry::unpack(entry.pack()));
+    }
+
+    #[test]
+    fn test_perft_kiwipete() {
+        assert_perft_table(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[(1, 48), (2, 2039), (3, 97862)],
+        );
+    }
+
+    #[test]
+    fn test_perft_kiwipete_deep() {
+        // castling both ways, castling out of and through check, and rooks
+        // being captured on their home squares all show up from depth 4 on
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_perft_table(fen, &[(4, 4085603)]);
+
+        // the unhashed perft at depth 5 is left to `test_perft_deep`
+        let table = TranspositionTable::new(64);
+        let mut b = Board::from_fen(fen).unwrap();
+        assert_eq!(193690690, perft_hashed(5, &mut b, &table));
+
+        let mut b = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        table.clear();
+        assert_eq!(7594526, perft_hashed(5, &mut b, &table));
+    }
+
+    #[test]
+    fn test_perft_castling() {
+        assert_perft_table(
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            &[(1, 26), (2, 568), (3, 13744), (4, 314346)],
+        );
+    }
+
+    #[test]
+    fn test_perft_promotions() {
+        assert_perft_table(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[(1, 6), (2, 264), (3, 9467)],
+        );
+        assert_perft_table(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[(1, 44), (2, 1486), (3, 62379)],
+        );
+    }
+
+    #[test]
+    fn test_perft_middlegame() {
+        assert_perft_table(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[(1, 46), (2, 2079), (3, 89890)],
+        );
+    }
+
+    #[test]
+    #[ignore = "slow, run with `cargo test --release -- --ignored`"]
+    fn test_perft_deep() {
+        assert_perft_table(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[(5, 193690690)],
+        );
+        assert_perft_table("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", &[(5, 7594526)]);
+        assert_perft_table(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[(4, 422333), (5, 15833292)],
+        );
+        // en passant captures that give check, are pinned or would expose the king
+        assert_perft_table("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", &[(6, 1440467)]);
+        assert_perft_table("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", &[(6, 1134888)]);
+        assert_perft_table("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", &[(6, 1015133)]);
+    }
+
+    #[test]
+    fn test_perft_en_passant_pin() {
+        // en passant captures that would expose the king along the fifth rank
+        assert_perft_table(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[(1, 14), (2, 191), (3, 2812), (4, 43238), (5, 674624)],
+        );
+    }
 }
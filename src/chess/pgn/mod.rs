@@ -0,0 +1,196 @@
+//! Reading and writing games in Portable Game Notation.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use crate::chess::board::fen::FenError;
+use crate::chess::board::r#move::Move;
+use crate::chess::board::san::SanError;
+use crate::chess::board::setup::{default_setup, DEFAULT_FEN};
+use crate::chess::board::Board;
+
+pub mod reader;
+pub mod writer;
+
+pub use reader::PgnReader;
+pub use writer::PgnWriter;
+
+/// The tags every PGN game should have, in the order of the export format.
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Game {
+    /// The tag pairs in the order they appeared.
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PgnMove>,
+    /// The game termination marker, one of `1-0`, `0-1`, `1/2-1/2` and `*`.
+    pub result: String,
+}
+
+/// A move of the mainline or of a variation, together with its annotations.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PgnMove {
+    pub mov: Move,
+    /// Numeric annotation glyphs, e.g. `1` for `$1` or `!`.
+    pub nags: Vec<u8>,
+    /// A comment in front of the move. This only occurs at the start of the
+    /// game or of a variation, anywhere else it belongs to the previous move.
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    /// Alternatives to this move, each starting in the position before it.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    pub fn new(mov: Move) -> Self {
+        Self {
+            mov,
+            nags: Vec::new(),
+            comment_before: None,
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+impl Game {
+    /// A game without moves, starting from the given position.
+    ///
+    /// The seven tag roster is filled with unknown values, and `SetUp` and
+    /// `FEN` tags are added if the position isn't the default one.
+    pub fn new(start: &Board) -> Self {
+        let mut game = Game {
+            tags: Vec::new(),
+            moves: Vec::new(),
+            result: "*".to_string(),
+        };
+        for name in SEVEN_TAG_ROSTER {
+            game.set_tag(name, unknown_tag_value(name));
+        }
+        let fen = start.to_fen();
+        if fen != DEFAULT_FEN {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &fen);
+        }
+        game
+    }
+
+    /// Replaces the value of the tag with the given name, or adds the tag.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// The value of the first tag with the given name.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The position the game started from, which is the one of the `FEN` tag if
+    /// there is one, and [`default_setup`] otherwise.
+    pub fn starting_position(&self) -> Result<Board, FenError> {
+        match self.tag("FEN") {
+            Some(fen) => Board::from_fen(fen),
+            None => {
+                let mut b = Board::new();
+                b.populate(default_setup);
+                Ok(b)
+            }
+        }
+    }
+
+    /// The moves of the game, without any variations.
+    pub fn mainline(&self) -> impl Iterator<Item = Move> + '_ {
+        self.moves.iter().map(|m| m.mov)
+    }
+}
+
+fn unknown_tag_value(name: &str) -> &'static str {
+    match name {
+        "Date" => "????.??.??",
+        "Result" => "*",
+        _ => "?",
+    }
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(io::Error),
+    /// The text doesn't follow the PGN grammar, e.g. an unterminated string.
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A move that can't be parsed or is illegal in the position.
+    Move {
+        line: usize,
+        column: usize,
+        error: SanError,
+    },
+    /// The `FEN` tag doesn't describe a valid position.
+    Fen {
+        line: usize,
+        column: usize,
+        error: FenError,
+    },
+}
+
+impl PgnError {
+    /// The line and column, both starting at 1, where the error occurred.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            PgnError::Io(_) => None,
+            PgnError::Syntax { line, column, .. }
+            | PgnError::Move { line, column, .. }
+            | PgnError::Fen { line, column, .. } => Some((*line, *column)),
+        }
+    }
+}
+
+impl Display for PgnError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnError::Io(e) => write!(f, "{}", e),
+            PgnError::Syntax {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            PgnError::Move {
+                line,
+                column,
+                error,
+            } => write!(f, "line {}, column {}: {}", line, column, error),
+            PgnError::Fen {
+                line,
+                column,
+                error,
+            } => write!(f, "line {}, column {}: {}", line, column, error),
+        }
+    }
+}
+
+impl Error for PgnError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PgnError::Io(e) => Some(e),
+            PgnError::Syntax { .. } => None,
+            PgnError::Move { error, .. } => Some(error),
+            PgnError::Fen { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for PgnError {
+    fn from(e: io::Error) -> Self {
+        PgnError::Io(e)
+    }
+}
@@ -0,0 +1,623 @@
+use std::io::{BufReader, Bytes, Read};
+
+use crate::chess::board::{Board, MakeMoveModification};
+use crate::chess::pgn::{Game, PgnError, PgnMove};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Token {
+    OpenBracket,
+    CloseBracket,
+    OpenParen,
+    CloseParen,
+    String(String),
+    /// Moves, move numbers, results, tag names and `!`/`?` annotations.
+    Symbol(String),
+    Comment(String),
+    Nag(u8),
+    Eof,
+}
+
+type Tag = (String, String);
+/// A line and column, both starting at 1.
+type Position = (usize, usize);
+
+/// A token and where it starts.
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+/// Splits the input into tokens, reading it byte by byte.
+struct Lexer<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    peeked_byte: Option<u8>,
+    peeked: Option<Spanned>,
+    /// The position of the next byte.
+    line: usize,
+    column: usize,
+}
+
+impl<R: Read> Lexer<R> {
+    fn new(read: R) -> Self {
+        Self {
+            bytes: BufReader::new(read).bytes(),
+            peeked_byte: None,
+            peeked: None,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn peek_byte(&mut self) -> Result<Option<u8>, PgnError> {
+        if self.peeked_byte.is_none() {
+            self.peeked_byte = self.bytes.next().transpose()?;
+        }
+        Ok(self.peeked_byte)
+    }
+
+    fn next_byte(&mut self) -> Result<Option<u8>, PgnError> {
+        let byte = self.peek_byte()?;
+        self.peeked_byte = None;
+        if byte == Some(b'\n') {
+            self.line += 1;
+            self.column = 1;
+        } else if byte.is_some() {
+            self.column += 1;
+        }
+        Ok(byte)
+    }
+
+    /// Consumes bytes as long as `f` returns `true` and returns them.
+    fn take_while(&mut self, f: impl Fn(u8) -> bool) -> Result<Vec<u8>, PgnError> {
+        let mut bytes = Vec::new();
+        while let Some(b) = self.peek_byte()? {
+            if !f(b) {
+                break;
+            }
+            bytes.push(b);
+            self.next_byte()?;
+        }
+        Ok(bytes)
+    }
+
+    fn peek(&mut self) -> Result<&Spanned, PgnError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex()?);
+        }
+        Ok(self.peeked.as_ref().unwrap())
+    }
+
+    fn next(&mut self) -> Result<Spanned, PgnError> {
+        match self.peeked.take() {
+            Some(spanned) => Ok(spanned),
+            None => self.lex(),
+        }
+    }
+
+    fn lex(&mut self) -> Result<Spanned, PgnError> {
+        loop {
+            let (line, column) = (self.line, self.column);
+            let byte = match self.next_byte()? {
+                None => {
+                    return Ok(Spanned {
+                        token: Token::Eof,
+                        line,
+                        column,
+                    })
+                }
+                Some(b) => b,
+            };
+
+            let token = match byte {
+                b if b.is_ascii_whitespace() => continue,
+                // move numbers are followed by periods, which carry no meaning
+                b'.' => continue,
+                // escaped lines and rest of line comments
+                b'%' if column == 1 => {
+                    self.take_while(|b| b != b'\n')?;
+                    continue;
+                }
+                b';' => {
+                    self.take_while(|b| b != b'\n')?;
+                    continue;
+                }
+                // reserved for future expansion
+                b'<' => {
+                    self.take_while(|b| b != b'>')?;
+                    self.next_byte()?;
+                    continue;
+                }
+                b'[' => Token::OpenBracket,
+                b']' => Token::CloseBracket,
+                b'(' => Token::OpenParen,
+                b')' => Token::CloseParen,
+                b'{' => {
+                    let comment = self.take_while(|b| b != b'}')?;
+                    if self.next_byte()?.is_none() {
+                        return Err(syntax_error(line, column, "unterminated comment"));
+                    }
+                    // line breaks within comments carry no meaning
+                    let comment = String::from_utf8_lossy(&comment);
+                    Token::Comment(comment.split_whitespace().collect::<Vec<_>>().join(" "))
+                }
+                b'"' => Token::String(self.lex_string(line, column)?),
+                b'$' => {
+                    let digits = self.take_while(|b| b.is_ascii_digit())?;
+                    match String::from_utf8_lossy(&digits).parse() {
+                        Ok(nag) => Token::Nag(nag),
+                        Err(_) => return Err(syntax_error(line, column, "invalid NAG")),
+                    }
+                }
+                b'*' => Token::Symbol("*".to_string()),
+                b if b.is_ascii_alphanumeric() || b == b'!' || b == b'?' => {
+                    let rest = self
+                        .take_while(|b| b.is_ascii_alphanumeric() || b"_+#=:-/!?".contains(&b))?;
+                    let mut symbol = vec![b];
+                    symbol.extend(rest);
+                    Token::Symbol(String::from_utf8_lossy(&symbol).to_string())
+                }
+                _ => return Err(syntax_error(line, column, "unexpected character")),
+            };
+
+            return Ok(Spanned {
+                token,
+                line,
+                column,
+            });
+        }
+    }
+
+    fn lex_string(&mut self, line: usize, column: usize) -> Result<String, PgnError> {
+        let mut bytes = Vec::new();
+        loop {
+            match self.next_byte()? {
+                None | Some(b'\n') => {
+                    return Err(syntax_error(line, column, "unterminated string"))
+                }
+                Some(b'"') => return Ok(String::from_utf8_lossy(&bytes).to_string()),
+                Some(b'\\') => match self.next_byte()? {
+                    Some(b) => bytes.push(b),
+                    None => return Err(syntax_error(line, column, "unterminated string")),
+                },
+                Some(b) => bytes.push(b),
+            }
+        }
+    }
+}
+
+/// A line of moves that is being read, either the mainline or a variation.
+struct Line {
+    moves: Vec<PgnMove>,
+    /// The position after the last move.
+    board: Board,
+    /// Undoes the last move, which gives the position where variations of it start.
+    last_move: Option<MakeMoveModification>,
+    comment_before: Option<String>,
+}
+
+impl Line {
+    fn new(board: Board) -> Self {
+        Self {
+            moves: Vec::new(),
+            board,
+            last_move: None,
+            comment_before: None,
+        }
+    }
+}
+
+fn syntax_error(line: usize, column: usize, message: &str) -> PgnError {
+    PgnError::Syntax {
+        line,
+        column,
+        message: message.to_string(),
+    }
+}
+
+fn append_comment(target: &mut Option<String>, comment: String) {
+    match target {
+        None => *target = Some(comment),
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(&comment);
+        }
+    }
+}
+
+fn is_result(symbol: &str) -> bool {
+    matches!(symbol, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+/// The NAG of a `!`/`?` suffix annotation.
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+/// Reads games one after another from any [`Read`], so that not the whole
+/// input has to be kept in memory.
+///
+/// After an error, reading continues with the next game.
+///
+/// ```
+/// use libchess::chess::pgn::PgnReader;
+///
+/// let pgn = "[White \"Morphy\"]\n\n1. e4 e5 2. Nf3 {the king's knight} d6 1-0\n";
+/// for game in PgnReader::new(pgn.as_bytes()) {
+///     let game = game.unwrap();
+///     assert_eq!(Some("Morphy"), game.tag("White"));
+///     assert_eq!(4, game.moves.len());
+/// }
+/// ```
+pub struct PgnReader<R: Read> {
+    lexer: Lexer<R>,
+    recovering: bool,
+    done: bool,
+}
+
+impl<R: Read> PgnReader<R> {
+    pub fn new(read: R) -> Self {
+        Self {
+            lexer: Lexer::new(read),
+            recovering: false,
+            done: false,
+        }
+    }
+
+    /// Reads the next game, or `None` at the end of the input.
+    pub fn read_game(&mut self) -> Result<Option<Game>, PgnError> {
+        if self.lexer.peek()?.token == Token::Eof {
+            return Ok(None);
+        }
+
+        let mut game = Game::default();
+        let mut fen_position = (0, 0);
+        while self.lexer.peek()?.token == Token::OpenBracket {
+            self.lexer.next()?;
+            let (tag, position) = self.read_tag()?;
+            if tag.0 == "FEN" {
+                fen_position = position;
+            }
+            game.tags.push(tag);
+        }
+
+        let board = game.starting_position().map_err(|error| PgnError::Fen {
+            line: fen_position.0,
+            column: fen_position.1,
+            error,
+        })?;
+
+        let mut lines = vec![Line::new(board)];
+        loop {
+            let Spanned {
+                token,
+                line,
+                column,
+            } = self.lexer.next()?;
+            let in_variation = lines.len() > 1;
+            let current = lines.last_mut().unwrap();
+
+            match token {
+                Token::Eof | Token::OpenBracket if in_variation => {
+                    return Err(syntax_error(line, column, "unterminated variation"))
+                }
+                Token::Eof => break,
+                // a new game without a result for this one
+                Token::OpenBracket => {
+                    self.lexer.peeked = Some(Spanned {
+                        token,
+                        line,
+                        column,
+                    });
+                    break;
+                }
+                Token::Symbol(s) if is_result(&s) => {
+                    if in_variation {
+                        return Err(syntax_error(line, column, "result inside a variation"));
+                    }
+                    game.result = s;
+                    break;
+                }
+                // move numbers
+                Token::Symbol(s) if s.bytes().all(|b| b.is_ascii_digit()) => {}
+                Token::Symbol(s) if s.bytes().all(|b| b == b'!' || b == b'?') => {
+                    if let (Some(mov), Some(nag)) = (current.moves.last_mut(), suffix_nag(&s)) {
+                        mov.nags.push(nag);
+                    }
+                }
+                Token::Symbol(san) => {
+                    let mov = current
+                        .board
+                        .parse_san(&san)
+                        .map_err(|error| PgnError::Move {
+                            line,
+                            column,
+                            error,
+                        })?;
+                    let mut pgn_move = PgnMove::new(mov);
+                    pgn_move.comment_before = current.comment_before.take();
+                    let suffix = san.trim_end_matches(['+', '#']);
+                    let suffix = &suffix[suffix.trim_end_matches(['!', '?']).len()..];
+                    pgn_move.nags.extend(suffix_nag(suffix));
+
+                    current.moves.push(pgn_move);
+                    current.last_move = Some(current.board.make_move(mov));
+                }
+                Token::Nag(nag) => {
+                    if let Some(mov) = current.moves.last_mut() {
+                        mov.nags.push(nag);
+                    }
+                }
+                Token::Comment(comment) => match current.moves.last_mut() {
+                    Some(mov) => append_comment(&mut mov.comment, comment),
+                    None => append_comment(&mut current.comment_before, comment),
+                },
+                Token::OpenParen => match &current.last_move {
+                    Some(last_move) => {
+                        let mut board = current.board.clone();
+                        board.unmake_move(last_move.clone());
+                        lines.push(Line::new(board));
+                    }
+                    None => {
+                        let message = "variation without a preceding move";
+                        return Err(syntax_error(line, column, message));
+                    }
+                },
+                Token::CloseParen => {
+                    if !in_variation {
+                        return Err(syntax_error(line, column, "unmatched ')'"));
+                    }
+                    let variation = lines.pop().unwrap();
+                    if !variation.moves.is_empty() {
+                        // a variation can only be opened after a move
+                        let parent = lines.last_mut().unwrap().moves.last_mut().unwrap();
+                        parent.variations.push(variation.moves);
+                    }
+                }
+                Token::CloseBracket | Token::String(_) => {
+                    return Err(syntax_error(line, column, "unexpected token in movetext"))
+                }
+            }
+        }
+
+        if game.result.is_empty() {
+            game.result = game.tag("Result").unwrap_or("*").to_string();
+        }
+        game.moves = lines.pop().unwrap().moves;
+        Ok(Some(game))
+    }
+
+    /// Reads a tag pair after its `[`, together with the position of the value.
+    fn read_tag(&mut self) -> Result<(Tag, Position), PgnError> {
+        let name = self.lexer.next()?;
+        let value = self.lexer.next()?;
+        let close = self.lexer.next()?;
+        let position = (value.line, value.column);
+        match (name.token, value.token, close.token) {
+            (Token::Symbol(name), Token::String(value), Token::CloseBracket) => {
+                Ok(((name, value), position))
+            }
+            _ => Err(syntax_error(name.line, name.column, "malformed tag pair")),
+        }
+    }
+
+    /// Skips the rest of a game that couldn't be read, up to its result or the
+    /// tags of the next game.
+    fn skip_game(&mut self) -> Result<(), PgnError> {
+        let mut after_tag = false;
+        loop {
+            let spanned = match self.lexer.next() {
+                Ok(spanned) => spanned,
+                Err(e @ PgnError::Io(_)) => return Err(e),
+                // anything else within the broken game doesn't matter
+                Err(_) => continue,
+            };
+            match &spanned.token {
+                Token::Symbol(s) if is_result(s) => return Ok(()),
+                // tags of the broken game are followed by more tags, those of the
+                // next one by its movetext
+                Token::OpenBracket if spanned.column == 1 && !after_tag => {
+                    self.lexer.peeked = Some(spanned);
+                    return Ok(());
+                }
+                Token::Eof => {
+                    self.lexer.peeked = Some(spanned);
+                    return Ok(());
+                }
+                _ => {}
+            }
+            after_tag = spanned.token == Token::CloseBracket;
+        }
+    }
+}
+
+impl<R: Read> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.recovering {
+            self.recovering = false;
+            if let Err(e) = self.skip_game() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+
+        match self.read_game() {
+            Ok(Some(game)) => Some(Ok(game)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                match e {
+                    PgnError::Io(_) => self.done = true,
+                    _ => self.recovering = true,
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::r#move::{Flags, Move};
+    use crate::chess::board::san::SanError;
+    use crate::chess::board::square::Square::*;
+
+    fn read_all(pgn: &str) -> Vec<Result<Game, PgnError>> {
+        PgnReader::new(pgn.as_bytes()).collect()
+    }
+
+    #[test]
+    fn test_tags_and_moves() {
+        let pgn = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+[Annotator "someone \"quoted\""]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 1/2-1/2
+"#;
+        let games = read_all(pgn);
+        assert_eq!(1, games.len());
+        let game = games[0].as_ref().unwrap();
+
+        assert_eq!(8, game.tags.len());
+        assert_eq!(Some("Belgrade, Serbia JUG"), game.tag("Site"));
+        assert_eq!(Some("someone \"quoted\""), game.tag("Annotator"));
+        assert_eq!(None, game.tag("FEN"));
+        assert_eq!("1/2-1/2", game.result);
+
+        let moves: Vec<Move> = game.mainline().collect();
+        assert_eq!(10, moves.len());
+        assert_eq!(Move::new(E2, E4, Flags::PAWN_SPRINT), moves[0]);
+        assert_eq!(Move::new(E1, G1, Flags::CASTLE_KING), moves[8]);
+    }
+
+    #[test]
+    fn test_comments_nags_and_variations() {
+        let pgn = "{Start} 1. e4 $1 {best by test} e5 (1... c5 {Sicilian} 2. Nf3 (2. c3) d6) \
+                   (1... e6!?) 2. Nf3?! ; rest of line\n Nc6 *";
+        let games = read_all(pgn);
+        let game = games[0].as_ref().unwrap();
+
+        assert_eq!("*", game.result);
+        assert_eq!(4, game.moves.len());
+
+        let e4 = &game.moves[0];
+        assert_eq!(Some("Start".to_string()), e4.comment_before);
+        assert_eq!(Some("best by test".to_string()), e4.comment);
+        assert_eq!(vec![1], e4.nags);
+
+        let e5 = &game.moves[1];
+        assert_eq!(2, e5.variations.len());
+        let sicilian = &e5.variations[0];
+        assert_eq!(Move::new(C7, C5, Flags::PAWN_SPRINT), sicilian[0].mov);
+        assert_eq!(Some("Sicilian".to_string()), sicilian[0].comment);
+        assert_eq!(3, sicilian.len());
+        assert_eq!(
+            Move::new(C2, C3, Flags::QUIET),
+            sicilian[1].variations[0][0].mov
+        );
+        assert_eq!(vec![5], e5.variations[1][0].nags);
+
+        assert_eq!(vec![6], game.moves[2].nags);
+    }
+
+    #[test]
+    fn test_multiple_games() {
+        let pgn = "[White \"a\"]\n\n1. d4 d5 1-0\n\n[White \"b\"]\n\n1. c4 0-1\n\n[White \"c\"]\n\n1. Nf3\n";
+        let games: Vec<Game> = read_all(pgn).into_iter().map(Result::unwrap).collect();
+
+        assert_eq!(3, games.len());
+        assert_eq!(Some("b"), games[1].tag("White"));
+        assert_eq!("0-1", games[1].result);
+        // no result, not even in the tags
+        assert_eq!("*", games[2].result);
+        assert_eq!(1, games[2].moves.len());
+    }
+
+    #[test]
+    fn test_fen_tag() {
+        let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]\n\n1... Kd7 2. e4 *";
+        let games = read_all(pgn);
+        let game = games[0].as_ref().unwrap();
+
+        assert_eq!(
+            vec![
+                Move::new(E8, D7, Flags::QUIET),
+                Move::new(E2, E4, Flags::PAWN_SPRINT)
+            ],
+            game.mainline().collect::<Vec<_>>()
+        );
+
+        let games = read_all("[FEN \"8/8/8\"]\n\n*");
+        assert!(matches!(
+            games[0],
+            Err(PgnError::Fen {
+                line: 1,
+                column: 6,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_illegal_move_position_and_recovery() {
+        let pgn = "[White \"a\"]\n\n1. e4 e5\n2. Ke3 Nc6 1-0\n\n[White \"b\"]\n\n1. d4 *\n";
+        let games = read_all(pgn);
+
+        assert_eq!(2, games.len());
+        match &games[0] {
+            Err(e @ PgnError::Move { error, .. }) => {
+                assert_eq!(Some((4, 4)), e.position());
+                assert_eq!(&SanError::IllegalMove("Ke3".to_string()), error);
+            }
+            other => panic!("expected illegal move, got {:?}", other),
+        }
+        assert_eq!(Some("b"), games[1].as_ref().unwrap().tag("White"));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        for (pgn, position) in [
+            ("[White \"a]\n\n1. e4 *", (1, 8)),
+            ("1. e4 {unterminated", (1, 7)),
+            ("1. e4 (d4", (1, 10)),
+            ("1. e4 e5) *", (1, 9)),
+            ("(1. e4) *", (1, 1)),
+            ("[White a]\n*", (1, 2)),
+        ] {
+            let games = read_all(pgn);
+            match &games[0] {
+                Err(e @ PgnError::Syntax { .. }) => {
+                    assert_eq!(Some(position), e.position(), "{}", pgn)
+                }
+                other => panic!("expected syntax error for {}, got {:?}", pgn, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert!(read_all("").is_empty());
+        assert!(read_all("  \n\n").is_empty());
+    }
+}
@@ -0,0 +1,353 @@
+use std::io::{self, ErrorKind, Write};
+
+use crate::chess::board::piece::Color;
+use crate::chess::board::setup::DEFAULT_FEN;
+use crate::chess::board::Board;
+use crate::chess::pgn::{unknown_tag_value, Game, PgnMove, SEVEN_TAG_ROSTER};
+
+/// Export format lines are at most 79 characters long, so that they fit into
+/// 80 columns.
+const MAX_LINE_LENGTH: usize = 79;
+
+/// Writes games in the PGN export format.
+///
+/// The seven tag roster comes first, followed by `SetUp` and `FEN` if the game
+/// didn't start from the default position, and all other tags in alphabetical
+/// order. The movetext is wrapped to fit into 80 columns.
+pub struct PgnWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> PgnWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Writes a game followed by an empty line.
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] if the `FEN` tag is invalid or
+    /// the game contains an illegal move.
+    pub fn write_game(&mut self, game: &Game) -> io::Result<()> {
+        let invalid = |message: String| io::Error::new(ErrorKind::InvalidInput, message);
+        let start = game
+            .starting_position()
+            .map_err(|e| invalid(format!("invalid FEN tag: {}", e)))?;
+
+        // the moves are checked before anything is written, so that an
+        // invalid game doesn't leave a partial one in the output
+        let mut tokens = Tokens::default();
+        write_line(&mut tokens, &start, &game.moves, true).map_err(invalid)?;
+        tokens.push(result_or_unknown(game));
+
+        let mut text = String::new();
+        for (name, value) in export_tags(game, &start) {
+            text.push_str(&format!("[{} \"{}\"]\n", name, escape(&value)));
+        }
+        text.push('\n');
+
+        let mut line = String::new();
+        for token in tokens.tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                text.push_str(&line);
+                text.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        text.push_str(&line);
+        text.push_str("\n\n");
+        self.out.write_all(text.as_bytes())
+    }
+}
+
+fn result_or_unknown(game: &Game) -> String {
+    if game.result.is_empty() {
+        "*".to_string()
+    } else {
+        game.result.clone()
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The tags in export order.
+fn export_tags(game: &Game, start: &Board) -> Vec<(String, String)> {
+    let mut tags: Vec<(String, String)> = SEVEN_TAG_ROSTER
+        .iter()
+        .map(|&name| {
+            let value = match name {
+                "Result" => result_or_unknown(game),
+                _ => game
+                    .tag(name)
+                    .unwrap_or(unknown_tag_value(name))
+                    .to_string(),
+            };
+            (name.to_string(), value)
+        })
+        .collect();
+
+    let fen = start.to_fen();
+    if fen != DEFAULT_FEN {
+        tags.push(("SetUp".to_string(), "1".to_string()));
+        tags.push(("FEN".to_string(), fen));
+    }
+
+    let mut others: Vec<(String, String)> = game
+        .tags
+        .iter()
+        .filter(|(name, _)| !SEVEN_TAG_ROSTER.contains(&name.as_str()))
+        .filter(|(name, _)| name != "SetUp" && name != "FEN")
+        .cloned()
+        .collect();
+    others.sort_by(|(a, _), (b, _)| a.cmp(b));
+    tags.extend(others);
+    tags
+}
+
+/// The movetext split at the places where a line may be wrapped.
+#[derive(Default)]
+struct Tokens {
+    tokens: Vec<String>,
+    /// Prepended to the next token, so that a `(` isn't separated from it.
+    prefix: String,
+}
+
+impl Tokens {
+    fn push(&mut self, token: String) {
+        let prefix = std::mem::take(&mut self.prefix);
+        self.tokens.push(prefix + &token);
+    }
+
+    fn push_comment(&mut self, comment: &str) {
+        let mut words: Vec<&str> = comment.split_whitespace().collect();
+        if words.is_empty() {
+            words.push("");
+        }
+        let last = words.len() - 1;
+        for (i, word) in words.into_iter().enumerate() {
+            let mut token = word.to_string();
+            if i == 0 {
+                token.insert(0, '{');
+            }
+            if i == last {
+                token.push('}');
+            }
+            self.push(token);
+        }
+    }
+}
+
+/// Adds the tokens of a line of moves that starts in `board`. The number of
+/// a black move is written if it is the first one, or if it follows a
+/// comment or a variation.
+fn write_line(
+    tokens: &mut Tokens,
+    board: &Board,
+    moves: &[PgnMove],
+    mut needs_number: bool,
+) -> Result<(), String> {
+    let mut board = board.clone();
+    for m in moves {
+        if let Some(comment) = &m.comment_before {
+            tokens.push_comment(comment);
+            needs_number = true;
+        }
+
+        if !board.legal_moves().contains(&m.mov) {
+            return Err(format!(
+                "illegal move {} in {}",
+                m.mov.to_uci(),
+                board.to_fen()
+            ));
+        }
+        match board.side_to_move() {
+            Color::White => tokens.push(format!("{}.", board.fullmove_number())),
+            Color::Black if needs_number => tokens.push(format!("{}...", board.fullmove_number())),
+            Color::Black => {}
+        }
+        tokens.push(board.san(&m.mov));
+        needs_number = false;
+
+        for nag in &m.nags {
+            tokens.push(format!("${}", nag));
+        }
+        if let Some(comment) = &m.comment {
+            tokens.push_comment(comment);
+            needs_number = true;
+        }
+        // an empty variation has nothing to write, and the reader drops them too
+        for variation in m.variations.iter().filter(|v| !v.is_empty()) {
+            tokens.prefix.push('(');
+            write_line(tokens, &board, variation, true)?;
+            tokens.tokens.last_mut().unwrap().push(')');
+            needs_number = true;
+        }
+
+        board.make_move(m.mov);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::setup::default_setup;
+    use crate::chess::pgn::PgnReader;
+
+    fn default_board() -> Board {
+        let mut b = Board::new();
+        b.populate(default_setup);
+        b
+    }
+
+    fn write(game: &Game) -> String {
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_game(game).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    fn game_with_moves(start: &Board, sans: &[&str]) -> Game {
+        let mut game = Game::new(start);
+        let mut b = start.clone();
+        for san in sans {
+            let mov = b.parse_san(san).unwrap();
+            game.moves.push(PgnMove::new(mov));
+            b.make_move(mov);
+        }
+        game
+    }
+
+    #[test]
+    fn test_write_game() {
+        let mut game = game_with_moves(&default_board(), &["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+        game.set_tag("White", "Kasparov, \"Garry\"");
+        game.set_tag("ECO", "C60");
+        game.set_tag("Annotator", "me");
+        game.result = "1-0".to_string();
+
+        assert_eq!(
+            "[Event \"?\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"Kasparov, \\\"Garry\\\"\"]\n\
+             [Black \"?\"]\n\
+             [Result \"1-0\"]\n\
+             [Annotator \"me\"]\n\
+             [ECO \"C60\"]\n\
+             \n\
+             1. e4 e5 2. Nf3 Nc6 3. Bb5 1-0\n\
+             \n",
+            write(&game)
+        );
+    }
+
+    #[test]
+    fn test_write_annotations() {
+        let mut game = game_with_moves(&default_board(), &["e4", "e5", "Nf3"]);
+        let mut after_e4 = default_board();
+        after_e4.make_move(game.moves[0].mov);
+        let mut c5 = PgnMove::new(after_e4.parse_san("c5").unwrap());
+        c5.comment = Some("Sicilian".to_string());
+        game.moves[0].nags.push(1);
+        game.moves[0].comment_before = Some("Start".to_string());
+        game.moves[0].comment = Some("best by test".to_string());
+        game.moves[1].variations.push(vec![c5]);
+
+        let pgn = write(&game);
+        assert!(pgn.ends_with(
+            "\n{Start} 1. e4 $1 {best by test} 1... e5 (1... c5 {Sicilian}) 2. Nf3 *\n\n"
+        ));
+
+        // reading it back gives the same game
+        let read = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(game.moves, read.moves);
+    }
+
+    #[test]
+    fn test_empty_variation() {
+        let mut game = game_with_moves(&default_board(), &["e4", "e5"]);
+        let mut after_e4 = default_board();
+        after_e4.make_move(game.moves[0].mov);
+        let c5 = PgnMove::new(after_e4.parse_san("c5").unwrap());
+        game.moves[0].variations.push(Vec::new());
+        game.moves[1].variations.push(Vec::new());
+        game.moves[1].variations.push(vec![c5]);
+
+        let pgn = write(&game);
+        assert!(pgn.ends_with("\n1. e4 e5 (1... c5) *\n\n"));
+
+        // reading it back gives the game without the empty variations
+        let read = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        game.moves[0].variations.clear();
+        game.moves[1].variations.remove(0);
+        assert_eq!(game.moves, read.moves);
+    }
+
+    #[test]
+    fn test_write_setup() {
+        let start = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 12").unwrap();
+        let game = game_with_moves(&start, &["Kd7", "e4"]);
+
+        let pgn = write(&game);
+        assert!(pgn.contains(
+            "[Result \"*\"]\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n\n"
+        ));
+        assert!(pgn.ends_with("\n12... Kd7 13. e4 *\n\n"));
+
+        // no setup tags for the default position
+        let pgn = write(&Game::new(&default_board()));
+        assert!(!pgn.contains("SetUp"));
+        assert!(!pgn.contains("FEN"));
+    }
+
+    #[test]
+    fn test_line_wrapping() {
+        let mut sans = Vec::new();
+        for _ in 0..20 {
+            sans.extend(["Nf3", "Nf6", "Ng1", "Ng8"]);
+        }
+        let mut game = game_with_moves(&default_board(), &sans);
+        game.moves[3].comment = Some("a rather long comment ".repeat(10));
+
+        let pgn = write(&game);
+        let movetext: Vec<&str> = pgn.split("\n\n").nth(1).unwrap().lines().collect();
+        assert!(movetext.len() > 5);
+        assert!(movetext.iter().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert!(movetext
+            .iter()
+            .all(|line| !line.starts_with(' ') && !line.ends_with(' ')));
+
+        let read = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(game.moves.len(), read.moves.len());
+        assert_eq!(
+            Some("a rather long comment ".repeat(10).trim_end()),
+            read.moves[3].comment.as_deref()
+        );
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let mut game = Game::new(&default_board());
+        game.moves.push(PgnMove::new(
+            default_board().parse_uci_move("e2e4").unwrap(),
+        ));
+        game.moves.push(PgnMove::new(
+            default_board().parse_uci_move("d2d4").unwrap(),
+        ));
+
+        let mut writer = PgnWriter::new(Vec::new());
+        let err = writer.write_game(&game).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        assert!(writer.into_inner().is_empty());
+    }
+}
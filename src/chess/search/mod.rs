@@ -0,0 +1,701 @@
+//! Finding the best move with a negamax alpha-beta search and iterative deepening.
+
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::chess::board::move_list::MoveList;
+use crate::chess::board::piece::Kind;
+use crate::chess::board::r#move::Move;
+use crate::chess::board::see::piece_value;
+use crate::chess::board::Board;
+use crate::chess::eval;
+use crate::chess::nnue;
+
+pub mod ordering;
+pub mod tt;
+
+use ordering::{is_quiet, CounterMoves, HistoryTable, KillerMoves, MovePicker};
+pub use tt::TranspositionTable;
+use tt::{Bound, SearchEntry};
+
+/// The deepest the search goes, in plies from the root.
+pub const MAX_PLY: usize = 64;
+
+/// The score of being checkmated right now. Scores beyond `±(MATE - MAX_PLY)`
+/// are mates, and the distance to `±MATE` is the number of plies until mate.
+const MATE: i32 = 32_000;
+const INFINITY: i32 = MATE + 1;
+
+/// How much a capture may gain on top of the captured piece, e.g. through the
+/// position, before quiescence search gives up on it.
+const DELTA_MARGIN: i32 = 200;
+
+/// How many nodes are searched between two checks of the clock.
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// When to stop searching. The search stops at whichever limit is reached
+/// first, and only when it is stopped from the outside if none is set.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct SearchLimits {
+    /// The maximum depth in plies, at most [`MAX_PLY`].
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+/// The outcome of a search from the point of view of the side to move.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Score {
+    Centipawns(i32),
+    /// Mate in the given number of moves, negative if the side to move gets mated.
+    Mate(i32),
+}
+
+impl Score {
+    fn from_internal(score: i32) -> Self {
+        if score > MATE - MAX_PLY as i32 {
+            Score::Mate((MATE - score + 1) / 2)
+        } else if score < -MATE + MAX_PLY as i32 {
+            Score::Mate(-(MATE + score) / 2)
+        } else {
+            Score::Centipawns(score)
+        }
+    }
+}
+
+/// Formats the score like UCI does, e.g. `cp 35` or `mate -2`.
+impl Display for Score {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Score::Centipawns(cp) => write!(f, "cp {}", cp),
+            Score::Mate(moves) => write!(f, "mate {}", moves),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SearchResult {
+    /// [`Move::NULL`] if there is no legal move.
+    pub best_move: Move,
+    pub score: Score,
+    /// The principal variation, starting with the best move.
+    pub pv: Vec<Move>,
+    /// The depth of the last completed iteration.
+    pub depth: u32,
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+/// Searches `board` until one of the `limits` is reached or `stop` is set.
+pub fn search(board: &Board, limits: &SearchLimits, stop: &AtomicBool) -> SearchResult {
+    Searcher::new(board, limits, stop).run(|_| {})
+}
+
+/// A single search, which deepens iteratively and reports after every
+/// completed iteration.
+pub struct Searcher<'a> {
+    board: Board,
+    /// The Zobrist keys of the positions before the current one, both from
+    /// the game and from the search, to detect repetitions.
+    history: Vec<u64>,
+    limits: &'a SearchLimits,
+    stop: &'a AtomicBool,
+    table: Option<&'a TranspositionTable>,
+    stopped: bool,
+    start: Instant,
+    nodes: u64,
+    /// `pv[ply]` is the best line found from `ply` on, of length `pv_len[ply]`.
+    pv: [[Move; MAX_PLY]; MAX_PLY],
+    pv_len: [usize; MAX_PLY],
+    /// The principal variation of the previous iteration, which is searched first.
+    previous_pv: Vec<Move>,
+    /// Whether the moves made so far are the start of `previous_pv`.
+    following_pv: bool,
+    killers: KillerMoves,
+    counter_moves: CounterMoves,
+    quiet_history: HistoryTable,
+}
+
+impl<'a> Searcher<'a> {
+    pub fn new(board: &Board, limits: &'a SearchLimits, stop: &'a AtomicBool) -> Self {
+        Self {
+            board: board.clone(),
+            history: Vec::new(),
+            limits,
+            stop,
+            table: None,
+            stopped: false,
+            start: Instant::now(),
+            nodes: 0,
+            pv: [[Move::NULL; MAX_PLY]; MAX_PLY],
+            pv_len: [0; MAX_PLY],
+            previous_pv: Vec::new(),
+            following_pv: false,
+            killers: KillerMoves::default(),
+            counter_moves: CounterMoves::default(),
+            quiet_history: HistoryTable::default(),
+        }
+    }
+
+    /// Sets the Zobrist keys of the positions before this one in the game, so
+    /// that the search sees repetitions.
+    pub fn with_history(mut self, history: &[u64]) -> Self {
+        self.history = history.to_vec();
+        self
+    }
+
+    /// Lets the search look up and store positions in `table`, which may be
+    /// shared with other searches.
+    pub fn with_table(mut self, table: &'a TranspositionTable) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    /// Searches until a limit is reached or the search is stopped, calling
+    /// `on_iteration` with the result of every completed iteration.
+    ///
+    /// An iteration that is cut short is thrown away, unless not even the
+    /// first one completed, in which case the first legal move is returned.
+    pub fn run(&mut self, mut on_iteration: impl FnMut(&SearchResult)) -> SearchResult {
+        self.start = Instant::now();
+        let max_depth = self
+            .limits
+            .depth
+            .map_or(MAX_PLY as u32, |d| d.clamp(1, MAX_PLY as u32 - 1));
+
+        let mut result = SearchResult {
+            best_move: self
+                .board
+                .legal_moves()
+                .first()
+                .copied()
+                .unwrap_or(Move::NULL),
+            score: Score::Centipawns(0),
+            pv: Vec::new(),
+            depth: 0,
+            nodes: 0,
+            time: Duration::ZERO,
+        };
+
+        for depth in 1..=max_depth {
+            self.following_pv = true;
+            let score = self.negamax(depth, 0, -INFINITY, INFINITY);
+            if self.stopped {
+                break;
+            }
+
+            let pv = self.pv[0][..self.pv_len[0]].to_vec();
+            result = SearchResult {
+                best_move: pv.first().copied().unwrap_or(Move::NULL),
+                score: Score::from_internal(score),
+                pv: pv.clone(),
+                depth,
+                nodes: self.nodes,
+                time: self.start.elapsed(),
+            };
+            on_iteration(&result);
+            self.previous_pv = pv;
+
+            // a forced mate won't be found any faster by searching deeper
+            if matches!(result.score, Score::Mate(_)) || result.best_move.is_null() {
+                break;
+            }
+        }
+
+        result.nodes = self.nodes;
+        result.time = self.start.elapsed();
+        result
+    }
+
+    fn negamax(&mut self, depth: u32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_len[ply] = 0;
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        if ply > 0 && self.is_draw() {
+            return 0;
+        }
+        if depth == 0 {
+            return self.quiescence(ply, alpha, beta);
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate(&self.board);
+        }
+
+        let position_key = self.board.zobrist_key();
+        let mut table_move = Move::NULL;
+        if let Some(entry) = self.table.and_then(|t| t.probe(position_key)) {
+            table_move = entry.best_move;
+            // at the root, the move has to be found as well
+            if ply > 0 && entry.depth as u32 >= depth {
+                let score = score_from_table(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+
+        // without a table move, the previous principal variation takes its
+        // place, as long as the moves made so far are on it
+        let following_pv = self.following_pv;
+        let pv_move = self.previous_pv.get(ply).copied().unwrap_or(Move::NULL);
+        let hash_move = if table_move.is_null() && following_pv {
+            pv_move
+        } else {
+            table_move
+        };
+        let previous_move = self.board.last_move().unwrap_or(Move::NULL);
+        let counter_move = self.counter_moves.get(previous_move);
+        let mut picker = MovePicker::new(hash_move, self.killers.get(ply), counter_move);
+
+        let color = self.board.side_to_move();
+        let original_alpha = alpha;
+        let mut best_move = Move::NULL;
+        let mut legal_moves = 0;
+        let mut quiets_tried = MoveList::new();
+        while let Some(mov) = picker.next(&self.board, &self.quiet_history) {
+            let modification = self.board.make_move(mov);
+            if self.board.king_in_check(color) {
+                self.board.unmake_move(modification);
+                continue;
+            }
+            legal_moves += 1;
+
+            self.following_pv = following_pv && mov == pv_move;
+            self.history.push(position_key);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            self.history.pop();
+            self.board.unmake_move(modification);
+
+            if self.stopped {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = mov;
+                self.update_pv(ply, mov);
+                if alpha >= beta {
+                    if is_quiet(mov) {
+                        self.killers.store(ply, mov);
+                        self.counter_moves.store(previous_move, mov);
+                        self.quiet_history.update(color, mov, &quiets_tried, depth);
+                    }
+                    break;
+                }
+            }
+            if is_quiet(mov) {
+                quiets_tried.push(mov);
+            }
+        }
+
+        if legal_moves == 0 {
+            return if self.board.king_in_check(color) {
+                -MATE + ply as i32
+            } else {
+                0
+            };
+        }
+
+        if let Some(table) = self.table {
+            let bound = if alpha >= beta {
+                Bound::Lower
+            } else if alpha > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            let entry = SearchEntry {
+                depth: depth as u8,
+                bound,
+                score: score_to_table(alpha, ply),
+                best_move,
+            };
+            table.store(position_key, entry);
+        }
+        alpha
+    }
+
+    /// Searches captures until the position is quiet, so that the evaluation
+    /// doesn't miss a piece that is about to be taken. The side to move may
+    /// also stand pat, i.e. keep the static evaluation instead of capturing,
+    /// unless it is in check, in which case all evasions are searched.
+    fn quiescence(&mut self, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_len[ply] = 0;
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        let color = self.board.side_to_move();
+        let in_check = self.board.king_in_check(color);
+        let stand_pat = evaluate(&self.board);
+        if ply >= MAX_PLY - 1 || (!in_check && stand_pat >= beta) {
+            return stand_pat.min(beta);
+        }
+
+        let mut picker = if in_check {
+            MovePicker::new(Move::NULL, [Move::NULL; 2], Move::NULL)
+        } else {
+            alpha = alpha.max(stand_pat);
+            // captures that lose material are left out by the picker
+            MovePicker::captures()
+        };
+        let mut legal_moves = 0;
+        while let Some(mov) = picker.next(&self.board, &self.quiet_history) {
+            // delta pruning: even winning the piece for free can't raise alpha
+            if !in_check && stand_pat + self.capture_gain(mov) + DELTA_MARGIN <= alpha {
+                continue;
+            }
+
+            let modification = self.board.make_move(mov);
+            if self.board.king_in_check(color) {
+                self.board.unmake_move(modification);
+                continue;
+            }
+            legal_moves += 1;
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.board.unmake_move(modification);
+
+            if self.stopped {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                self.update_pv(ply, mov);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        if in_check && legal_moves == 0 {
+            return -MATE + ply as i32;
+        }
+        alpha
+    }
+
+    /// The material a capture wins before any recapture.
+    fn capture_gain(&self, mov: Move) -> i32 {
+        let captured = match self.board[mov.to()] {
+            _ if mov.is_en_passant() => piece_value(Kind::Pawn),
+            Some(p) => piece_value(p.kind()),
+            None => 0,
+        };
+        let promotion = mov
+            .promotion_kind()
+            .map_or(0, |kind| piece_value(kind) - piece_value(Kind::Pawn));
+        captured + promotion
+    }
+
+    fn update_pv(&mut self, ply: usize, mov: Move) {
+        self.pv[ply][0] = mov;
+        if ply + 1 < MAX_PLY {
+            let child_len = self.pv_len[ply + 1];
+            let (parent, child) = self.pv.split_at_mut(ply + 1);
+            parent[ply][1..=child_len].copy_from_slice(&child[0][..child_len]);
+            self.pv_len[ply] = child_len + 1;
+        } else {
+            self.pv_len[ply] = 1;
+        }
+    }
+
+    /// Positions that occurred before count as draws, since the side that is
+    /// better off would avoid repeating them.
+    fn is_draw(&self) -> bool {
+        let key = self.board.zobrist_key();
+        self.board.halfmove_clock() >= 100
+            || self.board.is_insufficient_material()
+            || self.history.iter().rev().any(|&k| k == key)
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+        // the first iteration always runs to completion, unless stopped from outside
+        let limits_apply = !self.previous_pv.is_empty();
+        if self.stop.load(Ordering::Relaxed)
+            || (limits_apply && self.limits.nodes.is_some_and(|n| self.nodes > n))
+            || (limits_apply
+                && self.nodes.is_multiple_of(TIME_CHECK_INTERVAL)
+                && self.limits.time.is_some_and(|t| self.start.elapsed() >= t))
+        {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+}
+
+/// Mate scores are stored relative to the position, rather than to the root,
+/// since the position can be reached at different plies.
+fn score_to_table(score: i32, ply: usize) -> i16 {
+    let score = if score > MATE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score < -MATE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    };
+    score as i16
+}
+
+fn score_from_table(score: i16, ply: usize) -> i32 {
+    let score = score as i32;
+    if score > MATE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score < -MATE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+/// The network's evaluation if the board has one, the hand-crafted one otherwise.
+fn evaluate(board: &Board) -> i32 {
+    nnue::evaluate(board).unwrap_or_else(|| eval::evaluate(board))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::setup::default_setup;
+    use crate::chess::nnue::{Network, FIXTURE_PATH};
+    use std::sync::Arc;
+
+    fn search_depth(fen: &str, depth: u32) -> SearchResult {
+        let board = Board::from_fen(fen).unwrap();
+        let limits = SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        };
+        search(&board, &limits, &AtomicBool::new(false))
+    }
+
+    #[test]
+    fn test_mate_in_one() {
+        let result = search_depth("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+        assert_eq!("a1a8", result.best_move.to_uci());
+        assert_eq!(Score::Mate(1), result.score);
+        // quiescence sees that the check can't be answered, which ends the search
+        assert_eq!(1, result.depth);
+    }
+
+    #[test]
+    fn test_mate_in_two() {
+        let result = search_depth("k7/8/1K6/8/8/8/8/1R6 w - - 0 1", 4);
+        assert_eq!(Score::Mate(2), result.score);
+        assert_eq!(3, result.pv.len());
+
+        // the side getting mated sees it coming
+        let result = search_depth("k7/8/1K6/8/8/8/8/1R5R b - - 0 1", 4);
+        assert_eq!(Score::Mate(-1), result.score);
+    }
+
+    #[test]
+    fn test_wins_material() {
+        // the knight on d5 is hanging
+        let result = search_depth("4k3/8/8/3n4/8/8/3Q4/4K3 w - - 0 1", 3);
+        assert_eq!("d2d5", result.best_move.to_uci());
+        assert!(matches!(result.score, Score::Centipawns(cp) if cp >= 800));
+    }
+
+    #[test]
+    fn test_network() {
+        let mut board = Board::from_fen("4k3/8/8/3n4/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let network = Network::load(FIXTURE_PATH).unwrap();
+        board.set_network(Some(Arc::new(network)));
+        assert_eq!(nnue::evaluate(&board), Some(evaluate(&board)));
+
+        let limits = SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        };
+        let result = search(&board, &limits, &AtomicBool::new(false));
+        assert_eq!("d2d5", result.best_move.to_uci());
+        assert!(matches!(result.score, Score::Centipawns(cp) if cp >= 800));
+    }
+
+    #[test]
+    fn test_quiescence() {
+        // at depth 1, taking the pawn looks good until the recapture is seen
+        let result = search_depth("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1", 1);
+        assert_ne!("d1d5", result.best_move.to_uci());
+        let board = Board::from_fen("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let pawn_won = evaluate(&board) + 82;
+        assert!(matches!(result.score, Score::Centipawns(cp) if cp < pawn_won));
+
+        // the hanging queen is taken before evaluating
+        let board = Board::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let stop = AtomicBool::new(false);
+        let limits = SearchLimits::default();
+        let mut searcher = Searcher::new(&board, &limits, &stop);
+        let mut after = board.clone();
+        after.make_move(board.parse_uci_move("d1d5").unwrap());
+        assert_eq!(
+            -evaluate(&after),
+            searcher.quiescence(0, -INFINITY, INFINITY)
+        );
+        assert!(searcher.nodes > 1);
+
+        // in check, there is no standing pat
+        let board = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        let mut searcher = Searcher::new(&board, &limits, &stop);
+        assert_eq!(-MATE, searcher.quiescence(0, -INFINITY, INFINITY));
+    }
+
+    #[test]
+    fn test_pv_is_legal() {
+        let mut board = Board::new();
+        board.populate(default_setup);
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..SearchLimits::default()
+        };
+        let mut iterations = Vec::new();
+        let result = Searcher::new(&board, &limits, &AtomicBool::new(false))
+            .run(|r| iterations.push(r.depth));
+
+        assert_eq!(vec![1, 2, 3, 4], iterations);
+        assert_eq!(4, result.pv.len());
+        assert_eq!(result.best_move, result.pv[0]);
+        for mov in result.pv {
+            assert!(board.legal_moves().contains(&mov));
+            board.make_move(mov);
+        }
+    }
+
+    #[test]
+    fn test_table() {
+        let board = Board::from_fen("k7/8/1K6/8/8/8/8/1R6 w - - 0 1").unwrap();
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..SearchLimits::default()
+        };
+        let stop = AtomicBool::new(false);
+        let table = TranspositionTable::new(1);
+
+        let plain = search(&board, &limits, &stop);
+        let hashed = Searcher::new(&board, &limits, &stop)
+            .with_table(&table)
+            .run(|_| {});
+        assert_eq!(Score::Mate(2), hashed.score);
+        assert_eq!(plain.score, hashed.score);
+        assert!(table.probe(board.zobrist_key()).is_some());
+
+        // the mate is still found when all the positions are already known
+        let again = Searcher::new(&board, &limits, &stop)
+            .with_table(&table)
+            .run(|_| {});
+        assert_eq!(Score::Mate(2), again.score);
+        assert_eq!(hashed.best_move, again.best_move);
+
+        // king moves transpose a lot, which the table saves nodes on
+        let board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        let table = TranspositionTable::new(1);
+        let plain = search(&board, &limits, &stop);
+        let hashed = Searcher::new(&board, &limits, &stop)
+            .with_table(&table)
+            .run(|_| {});
+        assert!(hashed.nodes < plain.nodes);
+    }
+
+    #[test]
+    fn test_table_scores() {
+        assert_eq!(MATE - 3, score_to_table(MATE - 5, 2) as i32);
+        assert_eq!(MATE - 5, score_from_table(score_to_table(MATE - 5, 2), 2));
+        assert_eq!(-MATE + 2, score_from_table(score_to_table(-MATE + 7, 5), 0));
+        assert_eq!(-120, score_from_table(score_to_table(-120, 9), 3));
+    }
+
+    #[test]
+    fn test_no_legal_moves() {
+        let result = search_depth("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1", 3);
+        assert!(result.best_move.is_null());
+        assert_eq!(Score::Mate(0), result.score);
+
+        let result = search_depth("k7/8/1Q6/8/8/8/8/7K b - - 0 1", 3);
+        assert!(result.best_move.is_null());
+        assert_eq!(Score::Centipawns(0), result.score);
+    }
+
+    #[test]
+    fn test_draws() {
+        let board = Board::from_fen("k7/8/8/8/8/8/8/K6R w - - 0 1").unwrap();
+        let limits = SearchLimits::default();
+        let stop = AtomicBool::new(false);
+        assert!(!Searcher::new(&board, &limits, &stop).is_draw());
+
+        // going back and forth repeats the position
+        let mut history = Vec::new();
+        let mut b = board.clone();
+        for uci in ["h1h2", "a8b8", "h2h1", "b8a8"] {
+            history.push(b.zobrist_key());
+            let mov = b.parse_uci_move(uci).unwrap();
+            b.make_move(mov);
+        }
+        let searcher = Searcher::new(&b, &limits, &stop).with_history(&history);
+        assert!(searcher.is_draw());
+
+        let board = Board::from_fen("k7/8/8/8/8/8/8/K6R w - - 100 80").unwrap();
+        assert!(Searcher::new(&board, &limits, &stop).is_draw());
+        let board = Board::from_fen("k7/8/8/8/8/8/8/K6B w - - 0 1").unwrap();
+        assert!(Searcher::new(&board, &limits, &stop).is_draw());
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut board = Board::new();
+        board.populate(default_setup);
+
+        let limits = SearchLimits {
+            nodes: Some(5000),
+            ..SearchLimits::default()
+        };
+        let result = search(&board, &limits, &AtomicBool::new(false));
+        assert!(result.nodes < 10_000);
+        assert!(board.legal_moves().contains(&result.best_move));
+
+        let limits = SearchLimits {
+            time: Some(Duration::from_millis(50)),
+            ..SearchLimits::default()
+        };
+        let result = search(&board, &limits, &AtomicBool::new(false));
+        assert!(result.time < Duration::from_secs(1));
+        assert!(board.legal_moves().contains(&result.best_move));
+    }
+
+    #[test]
+    fn test_stop_flag() {
+        let mut board = Board::new();
+        board.populate(default_setup);
+        let stop = AtomicBool::new(false);
+
+        let result = std::thread::scope(|s| {
+            let handle = s.spawn(|| search(&board, &SearchLimits::default(), &stop));
+            std::thread::sleep(Duration::from_millis(50));
+            stop.store(true, Ordering::SeqCst);
+            handle.join().unwrap()
+        });
+        assert!(board.legal_moves().contains(&result.best_move));
+
+        // once stopped, not even the first iteration completes
+        let result = search(&board, &SearchLimits::default(), &stop);
+        assert_eq!(0, result.depth);
+        assert!(board.legal_moves().contains(&result.best_move));
+    }
+
+    #[test]
+    fn test_score_display() {
+        assert_eq!("cp -35", Score::Centipawns(-35).to_string());
+        assert_eq!("mate 3", Score::Mate(3).to_string());
+        assert_eq!(Score::Mate(1), Score::from_internal(MATE - 1));
+        assert_eq!(Score::Mate(2), Score::from_internal(MATE - 3));
+        assert_eq!(Score::Mate(-1), Score::from_internal(-MATE + 2));
+    }
+}
@@ -0,0 +1,450 @@
+//! Move ordering, which decides how early the search finds the moves that
+//! cause cutoffs, and with that how much of the tree it can skip.
+
+use crate::chess::board::move_list::{MoveList, MAX_MOVES};
+use crate::chess::board::piece::{Color, Kind};
+use crate::chess::board::r#move::Move;
+use crate::chess::board::{Board, MoveFilter};
+use crate::chess::search::MAX_PLY;
+
+/// History scores stay within `±MAX_HISTORY`.
+const MAX_HISTORY: i32 = 16_384;
+
+/// Whether a move is neither a capture nor a promotion. Only those are
+/// remembered as killers, countermoves and in the history.
+pub fn is_quiet(mov: Move) -> bool {
+    !mov.is_capture() && !mov.is_promotion()
+}
+
+/// Most valuable victim, least valuable attacker: captures of valuable pieces
+/// come first, and among those the ones with the least valuable piece.
+pub fn mvv_lva(board: &Board, mov: Move) -> i32 {
+    let rank = |kind: Kind| match kind {
+        Kind::Pawn => 1,
+        Kind::Knight => 2,
+        Kind::Bishop => 3,
+        Kind::Rook => 4,
+        Kind::Queen => 5,
+        Kind::King => 6,
+    };
+    let victim = match board[mov.to()] {
+        _ if mov.is_en_passant() => rank(Kind::Pawn),
+        Some(p) => rank(p.kind()),
+        None => 0,
+    };
+    let attacker = board[mov.from()].map_or(0, |p| rank(p.kind()));
+    let promotion = mov.promotion_kind().map_or(0, rank);
+    (victim + promotion) * 8 - attacker
+}
+
+/// Two quiet moves per ply that caused a cutoff in a sibling position, and
+/// therefore are likely to do so again.
+#[derive(Debug, Clone)]
+pub struct KillerMoves {
+    killers: [[Move; 2]; MAX_PLY],
+}
+
+impl Default for KillerMoves {
+    fn default() -> Self {
+        Self {
+            killers: [[Move::NULL; 2]; MAX_PLY],
+        }
+    }
+}
+
+impl KillerMoves {
+    pub fn get(&self, ply: usize) -> [Move; 2] {
+        self.killers[ply]
+    }
+
+    /// Makes `mov` the first killer of the ply, and the previous first one the second.
+    pub fn store(&mut self, ply: usize, mov: Move) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != mov {
+            killers[1] = killers[0];
+            killers[0] = mov;
+        }
+    }
+}
+
+/// The quiet move that refuted a move the last time, indexed by the origin
+/// and target square of the move it answers.
+#[derive(Debug, Clone)]
+pub struct CounterMoves {
+    moves: Box<[[Move; 64]; 64]>,
+}
+
+impl Default for CounterMoves {
+    fn default() -> Self {
+        Self {
+            moves: Box::new([[Move::NULL; 64]; 64]),
+        }
+    }
+}
+
+impl CounterMoves {
+    /// [`Move::NULL`] if there is no countermove for `previous`.
+    pub fn get(&self, previous: Move) -> Move {
+        self.moves[previous.from() as usize][previous.to() as usize]
+    }
+
+    pub fn store(&mut self, previous: Move, mov: Move) {
+        self.moves[previous.from() as usize][previous.to() as usize] = mov;
+    }
+}
+
+/// How often quiet moves caused cutoffs, by color, origin and target square,
+/// also known as the butterfly board.
+#[derive(Debug, Clone)]
+pub struct HistoryTable {
+    scores: Box<[[[i32; 64]; 64]; 2]>,
+}
+
+impl Default for HistoryTable {
+    fn default() -> Self {
+        Self {
+            scores: Box::new([[[0; 64]; 64]; 2]),
+        }
+    }
+}
+
+impl HistoryTable {
+    pub fn get(&self, color: Color, mov: Move) -> i32 {
+        self.scores[color as usize][mov.from() as usize][mov.to() as usize]
+    }
+
+    /// Rewards `best`, which caused a cutoff at `depth`, and punishes the
+    /// quiet moves that were searched before it in vain.
+    pub fn update(&mut self, color: Color, best: Move, tried: &[Move], depth: u32) {
+        let bonus = (depth * depth).min(400) as i32;
+        self.add(color, best, bonus);
+        for &mov in tried {
+            self.add(color, mov, -bonus);
+        }
+    }
+
+    /// Adds `bonus`, but less the closer the score already is to the bound,
+    /// so that scores stay within `±MAX_HISTORY` and newer results count more.
+    fn add(&mut self, color: Color, mov: Move, bonus: i32) {
+        let score = &mut self.scores[color as usize][mov.from() as usize][mov.to() as usize];
+        *score += bonus - *score * bonus.abs() / MAX_HISTORY;
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+enum Stage {
+    HashMove,
+    GenerateCaptures,
+    GoodCaptures,
+    GeneratePromotions,
+    Promotions,
+    FirstKiller,
+    SecondKiller,
+    CounterMove,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// Yields the pseudo-legal moves of a position in the order they should be
+/// searched, generating each kind of move only once the previous kinds are
+/// used up, so that an early cutoff saves generating the rest:
+///
+/// 1. the hash move, e.g. from a [`TranspositionTable`]
+/// 2. captures that don't lose material according to [`Board::see`], by [`mvv_lva`]
+/// 3. promotions that don't capture, queens first
+/// 4. the two [`KillerMoves`] of the ply
+/// 5. the countermove of the previous move, see [`CounterMoves`]
+/// 6. all other quiet moves by their [`HistoryTable`] score
+/// 7. captures that lose material
+///
+/// The board has to be in the same position on every call of [`MovePicker::next`].
+///
+/// [`TranspositionTable`]: crate::chess::search::TranspositionTable
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Move,
+    killers: [Move; 2],
+    counter_move: Move,
+    /// Only captures that don't lose material, for quiescence search.
+    captures_only: bool,
+    moves: MoveList,
+    scores: [i32; MAX_MOVES],
+    index: usize,
+    bad_captures: MoveList,
+}
+
+impl MovePicker {
+    pub fn new(hash_move: Move, killers: [Move; 2], counter_move: Move) -> Self {
+        Self {
+            stage: Stage::HashMove,
+            hash_move,
+            killers,
+            counter_move,
+            captures_only: false,
+            moves: MoveList::new(),
+            scores: [0; MAX_MOVES],
+            index: 0,
+            bad_captures: MoveList::new(),
+        }
+    }
+
+    /// A picker that only yields the captures that don't lose material.
+    pub fn captures() -> Self {
+        Self {
+            stage: Stage::GenerateCaptures,
+            captures_only: true,
+            ..Self::new(Move::NULL, [Move::NULL; 2], Move::NULL)
+        }
+    }
+
+    pub fn next(&mut self, board: &Board, history: &HistoryTable) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if !self.hash_move.is_null() && board.is_pseudo_legal(self.hash_move) {
+                        return Some(self.hash_move);
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    board.generate_filtered_into(MoveFilter::Captures, &mut self.moves);
+                    self.score(|mov| mvv_lva(board, mov));
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => match self.pick_best() {
+                    Some(mov) if mov == self.hash_move => {}
+                    Some(mov) if board.see(&mov) < 0 => {
+                        if !self.captures_only {
+                            self.bad_captures.push(mov);
+                        }
+                    }
+                    Some(mov) => return Some(mov),
+                    None if self.captures_only => self.stage = Stage::Done,
+                    None => self.stage = Stage::GeneratePromotions,
+                },
+                Stage::GeneratePromotions => {
+                    board.generate_filtered_into(MoveFilter::Promotions, &mut self.moves);
+                    self.score(|mov| mov.promotion_kind().map_or(0, promotion_order));
+                    self.stage = Stage::Promotions;
+                }
+                Stage::Promotions => match self.pick_best() {
+                    Some(mov) if mov == self.hash_move => {}
+                    Some(mov) => return Some(mov),
+                    None => self.stage = Stage::FirstKiller,
+                },
+                Stage::FirstKiller | Stage::SecondKiller | Stage::CounterMove => {
+                    let mov = match self.stage {
+                        Stage::FirstKiller => self.killers[0],
+                        Stage::SecondKiller => self.killers[1],
+                        _ => self.counter_move,
+                    };
+                    self.stage = match self.stage {
+                        Stage::FirstKiller => Stage::SecondKiller,
+                        Stage::SecondKiller => Stage::CounterMove,
+                        _ => Stage::GenerateQuiets,
+                    };
+                    let repeated = mov == self.hash_move
+                        || (self.stage > Stage::SecondKiller && mov == self.killers[0])
+                        || (self.stage > Stage::CounterMove && mov == self.killers[1]);
+                    if !mov.is_null() && !repeated && is_quiet(mov) && board.is_pseudo_legal(mov) {
+                        return Some(mov);
+                    }
+                }
+                Stage::GenerateQuiets => {
+                    board.generate_filtered_into(MoveFilter::Quiets, &mut self.moves);
+                    let color = board.side_to_move();
+                    self.score(|mov| history.get(color, mov));
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => match self.pick_best() {
+                    Some(mov) if self.is_special(mov) => {}
+                    Some(mov) => return Some(mov),
+                    None => {
+                        self.index = 0;
+                        self.stage = Stage::BadCaptures;
+                    }
+                },
+                Stage::BadCaptures => {
+                    let mov = self.bad_captures.get(self.index).copied();
+                    self.index += 1;
+                    if mov.is_none() {
+                        self.stage = Stage::Done;
+                    }
+                    return mov;
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    /// Whether the move was already yielded in one of the single move stages.
+    fn is_special(&self, mov: Move) -> bool {
+        mov == self.hash_move
+            || mov == self.killers[0]
+            || mov == self.killers[1]
+            || mov == self.counter_move
+    }
+
+    fn score(&mut self, score: impl Fn(Move) -> i32) {
+        for (i, &mov) in self.moves.iter().enumerate() {
+            self.scores[i] = score(mov);
+        }
+        self.index = 0;
+    }
+
+    /// Takes the remaining move with the highest score, which sorts only as
+    /// much of the list as is actually used.
+    fn pick_best(&mut self) -> Option<Move> {
+        let remaining = self.index..self.moves.len();
+        let best = remaining.max_by_key(|&i| self.scores[i])?;
+        self.moves.swap(self.index, best);
+        self.scores.swap(self.index, best);
+        self.index += 1;
+        Some(self.moves[self.index - 1])
+    }
+}
+
+fn promotion_order(kind: Kind) -> i32 {
+    match kind {
+        Kind::Queen => 3,
+        Kind::Knight => 2,
+        Kind::Rook => 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::r#move::Flags;
+    use crate::chess::board::square::Square::*;
+
+    fn pick_all(board: &Board, mut picker: MovePicker, history: &HistoryTable) -> Vec<Move> {
+        let mut moves = Vec::new();
+        while let Some(mov) = picker.next(board, history) {
+            moves.push(mov);
+        }
+        moves
+    }
+
+    fn uci(moves: &[Move]) -> Vec<String> {
+        moves.iter().map(|m| m.to_uci()).collect()
+    }
+
+    #[test]
+    fn test_picks_every_move_once() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+        ] {
+            let b = Board::from_fen(fen).unwrap();
+            let mut expected = b.generate_moves().to_vec();
+            let quiet = *expected.iter().find(|&&m| is_quiet(m)).unwrap();
+            let capture = *expected.iter().find(|m| m.is_capture()).unwrap();
+
+            for (hash, killers, counter) in [
+                (Move::NULL, [Move::NULL; 2], Move::NULL),
+                (capture, [quiet, capture], quiet),
+                (quiet, [quiet, Move::new(A1, H8, Flags::QUIET)], capture),
+            ] {
+                let picker = MovePicker::new(hash, killers, counter);
+                let mut moves = pick_all(&b, picker, &HistoryTable::default());
+                moves.sort();
+                expected.sort();
+                assert_eq!(expected, moves, "{}", fen);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stage_order() {
+        // the pawn can take the rook while promoting, the rook can take the
+        // knight and the queen can take a pawn, which is defended
+        let b = Board::from_fen("1r2k3/P7/2p5/1p5n/8/8/8/1Q2K2R w K - 0 1").unwrap();
+        let hash = b.parse_uci_move("e1g1").unwrap();
+        let killer = b.parse_uci_move("h1h4").unwrap();
+        let counter = b.parse_uci_move("b1b4").unwrap();
+        let mut history = HistoryTable::default();
+        history.update(Color::White, b.parse_uci_move("h1h3").unwrap(), &[], 10);
+
+        let picker = MovePicker::new(hash, [killer, Move::NULL], counter);
+        let moves = uci(&pick_all(&b, picker, &history));
+
+        #[rustfmt::skip]
+        let expected = [
+            "e1g1",
+            "a7b8q", "a7b8r", "a7b8b", "a7b8n", "h1h5",
+            "a7a8q", "a7a8n", "a7a8r", "a7a8b",
+            "h1h4", "b1b4", "h1h3",
+        ];
+        assert_eq!(expected, moves[..expected.len()]);
+        assert_eq!("b1b5", moves[moves.len() - 1]);
+        assert_eq!(b.generate_moves().len(), moves.len());
+    }
+
+    #[test]
+    fn test_captures_only() {
+        let b = Board::from_fen("1r2k3/P7/2p5/1p5n/8/8/8/1Q2K2R w K - 0 1").unwrap();
+        let moves = uci(&pick_all(
+            &b,
+            MovePicker::captures(),
+            &HistoryTable::default(),
+        ));
+        assert_eq!(vec!["a7b8q", "a7b8r", "a7b8b", "a7b8n", "h1h5"], moves);
+    }
+
+    #[test]
+    fn test_mvv_lva() {
+        let b = Board::from_fen("3qk3/8/2p5/1p1r4/2P1P3/8/8/1Q2K3 w - - 0 1").unwrap();
+        let score = |uci: &str| mvv_lva(&b, b.parse_uci_move(uci).unwrap());
+        assert_eq!(score("c4d5"), score("e4d5"));
+        assert!(score("e4d5") > score("c4b5"));
+        assert!(score("c4b5") > score("b1b5"));
+    }
+
+    #[test]
+    fn test_killers() {
+        let mut killers = KillerMoves::default();
+        let a = Move::new(A2, A3, Flags::QUIET);
+        let b = Move::new(B2, B3, Flags::QUIET);
+        let c = Move::new(C2, C3, Flags::QUIET);
+        killers.store(3, a);
+        killers.store(3, a);
+        assert_eq!([a, Move::NULL], killers.get(3));
+        killers.store(3, b);
+        killers.store(3, c);
+        assert_eq!([c, b], killers.get(3));
+        assert_eq!([Move::NULL; 2], killers.get(4));
+    }
+
+    #[test]
+    fn test_counter_moves() {
+        let mut counters = CounterMoves::default();
+        let previous = Move::new(E7, E5, Flags::PAWN_SPRINT);
+        let answer = Move::new(G1, F3, Flags::QUIET);
+        assert_eq!(Move::NULL, counters.get(previous));
+        counters.store(previous, answer);
+        assert_eq!(answer, counters.get(previous));
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = HistoryTable::default();
+        let good = Move::new(G1, F3, Flags::QUIET);
+        let bad = Move::new(A2, A3, Flags::QUIET);
+        history.update(Color::White, good, &[bad], 5);
+        assert_eq!(25, history.get(Color::White, good));
+        assert_eq!(-25, history.get(Color::White, bad));
+        assert_eq!(0, history.get(Color::Black, good));
+
+        for _ in 0..1000 {
+            history.update(Color::White, good, &[bad], 20);
+        }
+        assert!(history.get(Color::White, good) <= MAX_HISTORY);
+        assert!(history.get(Color::White, bad) >= -MAX_HISTORY);
+        assert!(history.get(Color::White, good) > MAX_HISTORY / 2);
+    }
+}
@@ -0,0 +1,297 @@
+//! A transposition table, which remembers what is known about positions that
+//! were already visited, so that they don't have to be searched again.
+
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::chess::board::r#move::Move;
+
+/// Entries are packed into the lower 56 bits of a slot, the upper 8 bits hold
+/// whether the slot is occupied and the age of the entry.
+const PAYLOAD_MASK: u64 = (1 << 56) - 1;
+const AGE_SHIFT: u32 = 56;
+const AGE_MASK: u8 = 0x7f;
+const OCCUPIED: u64 = 1 << 63;
+
+/// Something that can be stored in a [`TranspositionTable`].
+pub trait TableEntry: Copy {
+    /// How much work went into the entry. Deeper entries replace shallower ones.
+    fn depth(&self) -> u8;
+
+    /// Packs the entry into the lower 56 bits of a `u64`.
+    fn pack(&self) -> u64;
+
+    fn unpack(bits: u64) -> Self;
+}
+
+/// Whether the score of a [`SearchEntry`] is exact or only a bound, because
+/// the search of the position was cut off.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Bound {
+    Exact,
+    /// The score is at least this high.
+    Lower,
+    /// The score is at most this high.
+    Upper,
+}
+
+/// What the search found out about a position.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SearchEntry {
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: i16,
+    /// [`Move::NULL`] if no move was better than the others.
+    pub best_move: Move,
+}
+
+impl TableEntry for SearchEntry {
+    fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        self.best_move.bits() as u64
+            | (self.score as u16 as u64) << 16
+            | (self.depth as u64) << 32
+            | bound << 40
+    }
+
+    fn unpack(bits: u64) -> Self {
+        let bound = match (bits >> 40) & 0b11 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+        Self {
+            depth: (bits >> 32) as u8,
+            bound,
+            score: (bits >> 16) as u16 as i16,
+            best_move: Move::from_bits(bits as u16),
+        }
+    }
+}
+
+/// A slot holds the key xor-ed with the data, so that an entry whose two
+/// halves were written by different threads at the same time doesn't match
+/// any key and is ignored.
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// A fixed-size hash table keyed by [`Board::zobrist_key`], which can be
+/// shared between threads without locking.
+///
+/// Every key maps to a single slot. A new entry replaces the one in its slot
+/// if that belongs to the same position, is left over from an earlier search
+/// (see [`TranspositionTable::new_search`]), or isn't deeper than the new one.
+///
+/// [`Board::zobrist_key`]: crate::chess::board::Board::zobrist_key
+pub struct TranspositionTable<E: TableEntry = SearchEntry> {
+    slots: Box<[Slot]>,
+    age: AtomicU8,
+    entry: PhantomData<fn() -> E>,
+}
+
+impl<E: TableEntry> TranspositionTable<E> {
+    /// A table that takes up about `megabytes` of memory, but has at least one slot.
+    pub fn new(megabytes: usize) -> Self {
+        let len = (megabytes * 1024 * 1024 / size_of::<Slot>()).max(1);
+        Self {
+            slots: (0..len).map(|_| Slot::default()).collect(),
+            age: AtomicU8::new(0),
+            entry: PhantomData,
+        }
+    }
+
+    /// The number of entries the table can hold.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// Marks all entries as old, so that they are replaced first.
+    pub fn new_search(&self) {
+        let age = self.age.load(Ordering::Relaxed);
+        self.age
+            .store(age.wrapping_add(1) & AGE_MASK, Ordering::Relaxed);
+    }
+
+    fn slot(&self, key: u64) -> &Slot {
+        // maps the key onto the slots without a division
+        let index = (key as u128 * self.slots.len() as u128) >> 64;
+        &self.slots[index as usize]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<E> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        let stored_key = slot.key.load(Ordering::Relaxed) ^ data;
+        (data & OCCUPIED != 0 && stored_key == key).then(|| E::unpack(data & PAYLOAD_MASK))
+    }
+
+    pub fn store(&self, key: u64, entry: E) {
+        let slot = self.slot(key);
+        let age = self.age.load(Ordering::Relaxed);
+
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let old_key = slot.key.load(Ordering::Relaxed) ^ old_data;
+        let old_age = (old_data >> AGE_SHIFT) as u8 & AGE_MASK;
+        let replace = old_data & OCCUPIED == 0
+            || old_key == key
+            || old_age != age
+            || entry.depth() >= E::unpack(old_data & PAYLOAD_MASK).depth();
+        if !replace {
+            return;
+        }
+
+        let data = entry.pack() & PAYLOAD_MASK | (age as u64) << AGE_SHIFT | OCCUPIED;
+        slot.key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    /// How many of the first thousand slots hold an entry of the current
+    /// search, which UCI reports as `hashfull`.
+    pub fn hashfull(&self) -> usize {
+        let age = self.age.load(Ordering::Relaxed);
+        self.slots
+            .iter()
+            .take(1000)
+            .filter(|slot| {
+                let data = slot.data.load(Ordering::Relaxed);
+                data & OCCUPIED != 0 && (data >> AGE_SHIFT) as u8 & AGE_MASK == age
+            })
+            .count()
+            * 1000
+            / self.slots.len().min(1000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::board::r#move::Flags;
+    use crate::chess::board::square::Square::*;
+
+    fn entry(depth: u8, score: i16) -> SearchEntry {
+        SearchEntry {
+            depth,
+            bound: Bound::Lower,
+            score,
+            best_move: Move::new(E2, E4, Flags::PAWN_SPRINT),
+        }
+    }
+
+    #[test]
+    fn test_packing() {
+        for entry in [
+            entry(0, 0),
+            entry(63, -31_999),
+            SearchEntry {
+                depth: 255,
+                bound: Bound::Upper,
+                score: i16::MAX,
+                best_move: Move::new(B7, A8, Flags::PROMOTION_QUEEN | Flags::CAPTURE),
+            },
+            SearchEntry {
+                depth: 1,
+                bound: Bound::Exact,
+                score: -1,
+                best_move: Move::NULL,
+            },
+        ] {
+            assert_eq!(entry, SearchEntry::unpack(entry.pack()));
+            assert_eq!(0, entry.pack() & !PAYLOAD_MASK);
+        }
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let table = TranspositionTable::<SearchEntry>::new(1);
+        assert_eq!(1024 * 1024 / 16, table.capacity());
+        assert_eq!(None, table.probe(42));
+
+        table.store(42, entry(3, 100));
+        table.store(u64::MAX, entry(5, -100));
+        assert_eq!(Some(entry(3, 100)), table.probe(42));
+        assert_eq!(Some(entry(5, -100)), table.probe(u64::MAX));
+        assert_eq!(None, table.probe(43));
+
+        table.clear();
+        assert_eq!(None, table.probe(42));
+    }
+
+    #[test]
+    fn test_replacement() {
+        // with a single slot, all keys compete for it
+        let table = TranspositionTable::<SearchEntry>::new(0);
+        assert_eq!(1, table.capacity());
+
+        table.store(1, entry(5, 0));
+        table.store(2, entry(4, 0));
+        assert_eq!(Some(entry(5, 0)), table.probe(1));
+        assert_eq!(None, table.probe(2));
+
+        // the same position is always updated
+        table.store(1, entry(2, 7));
+        assert_eq!(Some(entry(2, 7)), table.probe(1));
+
+        table.store(2, entry(2, 0));
+        assert_eq!(Some(entry(2, 0)), table.probe(2));
+
+        // entries of earlier searches give way to shallower ones
+        table.store(3, entry(9, 0));
+        table.new_search();
+        table.store(4, entry(1, 0));
+        assert_eq!(None, table.probe(3));
+        assert_eq!(Some(entry(1, 0)), table.probe(4));
+    }
+
+    #[test]
+    fn test_hashfull() {
+        let table = TranspositionTable::<SearchEntry>::new(1);
+        assert_eq!(0, table.hashfull());
+        for key in 0..table.capacity() as u64 {
+            table.store(key << 48, entry(1, 0));
+        }
+        assert_eq!(1000, table.hashfull());
+
+        table.new_search();
+        assert_eq!(0, table.hashfull());
+    }
+
+    #[test]
+    fn test_concurrent_access() {
+        let table = TranspositionTable::<SearchEntry>::new(0);
+        std::thread::scope(|s| {
+            for thread in 0..4 {
+                let table = &table;
+                s.spawn(move || {
+                    for i in 0..10_000u64 {
+                        let key = i % 7;
+                        table.store(key, entry(thread, key as i16));
+                        // whatever is found belongs to the key it was stored with
+                        if let Some(found) = table.probe(key) {
+                            assert_eq!(key as i16, found.score);
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,553 @@
+//! The Universal Chess Interface, which lets chess GUIs talk to this crate as
+//! an engine over stdin and stdout.
+
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::chess::board::piece::Color;
+use crate::chess::board::setup::default_setup;
+use crate::chess::board::Board;
+use crate::chess::nnue::Network;
+use crate::chess::perft::perft;
+use crate::chess::search::{SearchLimits, SearchResult, Searcher, TranspositionTable};
+
+const DEFAULT_MOVE_OVERHEAD: u64 = 30;
+const DEFAULT_HASH: usize = 16;
+const MAX_HASH: usize = 4096;
+
+/// The parameters of a `go` command.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct GoParams {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+    pub infinite: bool,
+    /// Not part of UCI, but a common extension to run [`perft`] instead of a search.
+    pub perft: Option<usize>,
+}
+
+impl GoParams {
+    /// Parses the arguments after `go`, ignoring anything it doesn't understand.
+    pub fn parse(args: &[&str]) -> GoParams {
+        let mut params = GoParams::default();
+        let mut args = args.iter();
+        while let Some(&arg) = args.next() {
+            let mut value = || args.next().copied().unwrap_or_default();
+            // GUIs send negative clocks when a side is over its time
+            let millis = |v: &str| {
+                v.parse::<i64>()
+                    .ok()
+                    .map(|ms| Duration::from_millis(ms.max(0) as u64))
+            };
+            match arg {
+                "depth" => params.depth = value().parse().ok(),
+                "nodes" => params.nodes = value().parse().ok(),
+                "movetime" => params.movetime = millis(value()),
+                "wtime" => params.wtime = millis(value()),
+                "btime" => params.btime = millis(value()),
+                "winc" => params.winc = millis(value()),
+                "binc" => params.binc = millis(value()),
+                "movestogo" => params.movestogo = value().parse().ok(),
+                "perft" => params.perft = value().parse().ok(),
+                "infinite" => params.infinite = true,
+                _ => {}
+            }
+        }
+        params
+    }
+
+    /// How long the side to move may think, or `None` if there is no time limit.
+    pub fn time_budget(&self, color: Color, overhead: Duration) -> Option<Duration> {
+        if self.infinite {
+            return None;
+        }
+        if let Some(movetime) = self.movetime {
+            return Some(movetime.saturating_sub(overhead));
+        }
+
+        let (time, inc) = match color {
+            Color::White => (self.wtime, self.winc.unwrap_or_default()),
+            Color::Black => (self.btime, self.binc.unwrap_or_default()),
+        };
+        let time = match time {
+            Some(time) => time,
+            // only the other side's clock, so ours has no time left
+            None if self.wtime.is_some() || self.btime.is_some() => Duration::ZERO,
+            None => return None,
+        };
+        // without a time control, assume that the game lasts another 30 moves
+        let moves_left = self.movestogo.unwrap_or(30).max(1);
+        let budget = time / moves_left + inc * 3 / 4;
+        Some(budget.min(time.saturating_sub(overhead)))
+    }
+}
+
+/// Keeps the state between commands and runs searches on a separate thread,
+/// so that `stop` can be handled while searching.
+pub struct Engine<W: Write + Send + 'static> {
+    board: Board,
+    /// The Zobrist keys of the positions before the current one.
+    history: Vec<u64>,
+    move_overhead: Duration,
+    /// Shared with the search thread, and replaced when its size changes.
+    table: Arc<TranspositionTable>,
+    /// The network searches evaluate with, if one was loaded.
+    network: Option<Arc<Network>>,
+    out: Arc<Mutex<W>>,
+    stop: Arc<AtomicBool>,
+    search: Option<JoinHandle<()>>,
+}
+
+impl<W: Write + Send + 'static> Engine<W> {
+    pub fn new(out: W) -> Self {
+        let mut board = Board::new();
+        board.populate(default_setup);
+        Self {
+            board,
+            history: Vec::new(),
+            move_overhead: Duration::from_millis(DEFAULT_MOVE_OVERHEAD),
+            table: Arc::new(TranspositionTable::new(DEFAULT_HASH)),
+            network: None,
+            out: Arc::new(Mutex::new(out)),
+            stop: Arc::new(AtomicBool::new(false)),
+            search: None,
+        }
+    }
+
+    fn send(&self, line: &str) {
+        send(&self.out, line);
+    }
+
+    /// Handles a single command and returns `false` if the engine should quit.
+    pub fn handle(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (&command, args) = match tokens.split_first() {
+            None => return true,
+            Some(v) => v,
+        };
+
+        match command {
+            "uci" => {
+                self.send("id name libchess");
+                self.send("id author the libchess authors");
+                self.send(&format!(
+                    "option name Move Overhead type spin default {} min 0 max 5000",
+                    DEFAULT_MOVE_OVERHEAD
+                ));
+                self.send(&format!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH, MAX_HASH
+                ));
+                self.send("option name EvalFile type string default <empty>");
+                self.send("uciok");
+            }
+            "isready" => self.send("readyok"),
+            "ucinewgame" => {
+                self.stop_search();
+                self.board = Board::new();
+                self.board.populate(default_setup);
+                self.history.clear();
+                self.table.clear();
+            }
+            "position" => {
+                self.stop_search();
+                if let Err(message) = self.set_position(args) {
+                    self.send(&format!("info string {}", message));
+                }
+            }
+            "setoption" => {
+                self.stop_search();
+                self.set_option(args);
+            }
+            "go" => {
+                self.stop_search();
+                self.go(GoParams::parse(args));
+            }
+            "stop" => self.stop_search(),
+            "quit" => {
+                self.stop_search();
+                return false;
+            }
+            _ => self.send(&format!("info string unknown command '{}'", command)),
+        }
+        true
+    }
+
+    /// Handles `position startpos|fen <fen> [moves <move>...]`.
+    fn set_position(&mut self, args: &[&str]) -> Result<(), String> {
+        let moves_index = args.iter().position(|&a| a == "moves");
+        let (setup, moves) = match moves_index {
+            Some(i) => (&args[..i], &args[i + 1..]),
+            None => (args, &[][..]),
+        };
+
+        let mut board = match setup.split_first() {
+            Some((&"startpos", _)) => {
+                let mut b = Board::new();
+                b.populate(default_setup);
+                b
+            }
+            Some((&"fen", fen)) => Board::from_fen(&fen.join(" ")).map_err(|e| e.to_string())?,
+            _ => return Err("expected 'startpos' or 'fen'".to_string()),
+        };
+        let mut history = Vec::new();
+        for uci in moves {
+            let mov = board.parse_uci_move(uci).map_err(|e| e.to_string())?;
+            history.push(board.zobrist_key());
+            board.make_move(mov);
+        }
+
+        self.board = board;
+        self.history = history;
+        Ok(())
+    }
+
+    /// Handles `setoption name <name> [value <value>]`.
+    fn set_option(&mut self, args: &[&str]) {
+        let name_index = args.iter().position(|&a| a == "name");
+        let value_index = args.iter().position(|&a| a == "value");
+        let (name, value) = match (name_index, value_index) {
+            (Some(0), None) => (args[1..].join(" "), None),
+            (Some(0), Some(i)) => (args[1..i].join(" "), Some(args[i + 1..].join(" "))),
+            _ => {
+                self.send("info string expected 'setoption name <name> [value <value>]'");
+                return;
+            }
+        };
+
+        match (name.to_lowercase().as_str(), value) {
+            ("move overhead", Some(value)) => match value.parse() {
+                Ok(millis) => self.move_overhead = Duration::from_millis(millis),
+                Err(_) => self.send(&format!("info string invalid value '{}'", value)),
+            },
+            ("hash", Some(value)) => match value.parse::<usize>() {
+                Ok(megabytes) if (1..=MAX_HASH).contains(&megabytes) => {
+                    self.table = Arc::new(TranspositionTable::new(megabytes));
+                }
+                _ => self.send(&format!("info string invalid value '{}'", value)),
+            },
+            // an empty path goes back to the hand-crafted evaluation
+            ("evalfile", value) => match value.as_deref() {
+                None | Some("") | Some("<empty>") => self.network = None,
+                Some(path) => match Network::load(path) {
+                    Ok(network) => self.network = Some(Arc::new(network)),
+                    Err(e) => self.send(&format!("info string can't load '{}': {}", path, e)),
+                },
+            },
+            _ => self.send(&format!("info string unknown option '{}'", name)),
+        }
+    }
+
+    fn go(&mut self, params: GoParams) {
+        if let Some(depth) = params.perft {
+            self.run_perft(depth);
+            return;
+        }
+
+        self.stop.store(false, Ordering::SeqCst);
+        let mut board = self.board.clone();
+        board.set_network(self.network.clone());
+        let history = self.history.clone();
+        let limits = SearchLimits {
+            depth: params.depth,
+            nodes: params.nodes,
+            time: params.time_budget(board.side_to_move(), self.move_overhead),
+        };
+        let out = Arc::clone(&self.out);
+        let stop = Arc::clone(&self.stop);
+        let table = Arc::clone(&self.table);
+        table.new_search();
+
+        self.search = Some(thread::spawn(move || {
+            let result = Searcher::new(&board, &limits, &stop)
+                .with_history(&history)
+                .with_table(&table)
+                .run(|result| send(&out, &info(result, table.hashfull())));
+
+            // the GUI expects the best move only after it sent `stop`
+            if params.infinite {
+                // woken up by `stop_search`, parking again on spurious wakeups
+                while !stop.load(Ordering::SeqCst) {
+                    thread::park();
+                }
+            }
+            send(&out, &format!("bestmove {}", result.best_move.to_uci()));
+        }));
+    }
+
+    /// Prints the number of leaf nodes after every move, like `go perft` of
+    /// other engines, which helps to find move generation bugs.
+    fn run_perft(&mut self, depth: usize) {
+        let start = Instant::now();
+        let mut total = 0;
+        let mut board = self.board.clone();
+        for mov in board.legal_moves() {
+            let modification = board.make_move(mov);
+            let nodes = perft(depth.saturating_sub(1), &mut board);
+            board.unmake_move(modification);
+            total += nodes;
+            self.send(&format!("{}: {}", mov.to_uci(), nodes));
+        }
+        self.send("");
+        self.send(&format!("Nodes searched: {}", total));
+        self.send(&format!("Time: {:?}", start.elapsed()));
+    }
+
+    /// Stops a running search and waits until it sent its best move.
+    fn stop_search(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.search.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+fn send<W: Write>(out: &Mutex<W>, line: &str) {
+    let mut out = out.lock().unwrap();
+    let _ = writeln!(out, "{}", line);
+    let _ = out.flush();
+}
+
+/// The `info` line for a completed iteration of the search.
+fn info(result: &SearchResult, hashfull: usize) -> String {
+    let millis = result.time.as_millis() as u64;
+    let pv: Vec<String> = result.pv.iter().map(|m| m.to_uci()).collect();
+    format!(
+        "info depth {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+        result.depth,
+        result.score,
+        result.nodes,
+        result.nodes * 1000 / millis.max(1),
+        hashfull,
+        millis,
+        pv.join(" ")
+    )
+}
+
+/// Reads commands from `input` until `quit` or the end of the input.
+pub fn run<R: BufRead, W: Write + Send + 'static>(input: R, out: W) {
+    let mut engine = Engine::new(out);
+    for line in input.lines() {
+        match line {
+            Ok(line) if engine.handle(&line) => {}
+            _ => break,
+        }
+    }
+    engine.stop_search();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::nnue::FIXTURE_PATH;
+
+    /// Output that can still be read after the engine took it.
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedOutput {
+        fn lines(&self) -> Vec<String> {
+            let bytes = self.0.lock().unwrap();
+            String::from_utf8_lossy(&bytes)
+                .lines()
+                .map(|l| l.to_string())
+                .collect()
+        }
+    }
+
+    fn run_commands(commands: &str) -> Vec<String> {
+        let out = SharedOutput::default();
+        run(commands.as_bytes(), out.clone());
+        out.lines()
+    }
+
+    #[test]
+    fn test_handshake() {
+        let lines = run_commands("uci\nisready\nquit\n");
+
+        assert_eq!("id name libchess", lines[0]);
+        assert!(lines.contains(&"uciok".to_string()));
+        assert_eq!("readyok", lines.last().unwrap());
+    }
+
+    #[test]
+    fn test_go_returns_legal_move() {
+        let lines = run_commands("position startpos moves e2e4 e7e5\ngo depth 1\nisready\n");
+        let bestmove = lines.iter().find(|l| l.starts_with("bestmove")).unwrap();
+
+        let mut b = Board::new();
+        b.populate(default_setup);
+        b.make_move(b.parse_uci_move("e2e4").unwrap());
+        b.make_move(b.parse_uci_move("e7e5").unwrap());
+        assert!(b.parse_uci_move(&bestmove["bestmove ".len()..]).is_ok());
+    }
+
+    #[test]
+    fn test_go_reports_iterations() {
+        let out = SharedOutput::default();
+        let mut engine = Engine::new(out.clone());
+        engine.handle("position fen k7/8/1K6/8/8/8/8/1R6 w - - 0 1");
+        engine.handle("go depth 4");
+        engine.search.take().unwrap().join().unwrap();
+
+        let lines = out.lines();
+        assert!(lines[0].starts_with("info depth 1 score cp "));
+        assert!(lines[1].starts_with("info depth 2 score cp "));
+        assert!(lines[2].starts_with("info depth 3 score mate 2 "));
+        assert!(lines[2].ends_with(" pv b6c7 a8a7 b1a1"));
+        assert_eq!("bestmove b6c7", lines[3]);
+    }
+
+    #[test]
+    fn test_go_infinite_waits_for_stop() {
+        let out = SharedOutput::default();
+        let mut engine = Engine::new(out.clone());
+        engine.handle("position fen 4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        engine.handle("go infinite");
+        thread::sleep(Duration::from_millis(20));
+        assert!(!out.lines().iter().any(|l| l.starts_with("bestmove")));
+
+        engine.handle("stop");
+        assert!(out.lines().iter().any(|l| l.starts_with("bestmove")));
+    }
+
+    #[test]
+    fn test_position_errors() {
+        let lines = run_commands("position startpos moves e2e5\nposition fen 8/8\nposition\n");
+
+        assert_eq!(3, lines.len());
+        assert!(lines.iter().all(|l| l.starts_with("info string")));
+    }
+
+    #[test]
+    fn test_go_perft() {
+        let lines = run_commands("position startpos\ngo perft 3\n");
+
+        let per_move = lines
+            .iter()
+            .filter(|l| !l.starts_with("Time") && l.split(": ").next().unwrap().len() == 4);
+        assert_eq!(20, per_move.count());
+        assert!(lines.contains(&"Nodes searched: 8902".to_string()));
+    }
+
+    #[test]
+    fn test_setoption() {
+        let out = SharedOutput::default();
+        let mut engine = Engine::new(out.clone());
+        engine.handle("setoption name Move Overhead value 100");
+        assert_eq!(Duration::from_millis(100), engine.move_overhead);
+
+        engine.handle("setoption name Hash value 2");
+        assert_eq!(2 * 1024 * 1024 / 16, engine.table.capacity());
+        assert!(out.lines().is_empty());
+
+        engine.handle("setoption name Hash value 0");
+        engine.handle("setoption name Unknown value 1");
+        assert!(out.lines()[0].starts_with("info string invalid value"));
+        assert!(out.lines()[1].starts_with("info string unknown option"));
+    }
+
+    #[test]
+    fn test_setoption_malformed() {
+        let out = SharedOutput::default();
+        let mut engine = Engine::new(out.clone());
+        for command in [
+            "setoption",
+            "setoption value 5",
+            "setoption value 5 name Hash",
+            "setoption Hash value 5",
+        ] {
+            engine.handle(command);
+        }
+
+        let lines = out.lines();
+        assert_eq!(4, lines.len());
+        assert!(lines.iter().all(|l| l.starts_with("info string expected")));
+        assert_eq!(DEFAULT_HASH * 1024 * 1024 / 16, engine.table.capacity());
+    }
+
+    #[test]
+    fn test_setoption_eval_file() {
+        let out = SharedOutput::default();
+        let mut engine = Engine::new(out.clone());
+        engine.handle(&format!("setoption name EvalFile value {}", FIXTURE_PATH));
+        assert_eq!(Some(16), engine.network.as_ref().map(|n| n.hidden_size()));
+        assert!(out.lines().is_empty());
+
+        engine.handle("setoption name EvalFile value does/not/exist.nnue");
+        assert!(out.lines()[0].starts_with("info string can't load 'does/not/exist.nnue'"));
+        // a file that can't be loaded keeps the previous network
+        assert!(engine.network.is_some());
+
+        engine.handle("setoption name EvalFile value <empty>");
+        assert!(engine.network.is_none());
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let params = GoParams::parse(&["wtime", "60000", "btime", "1000", "winc", "1000"]);
+        let overhead = Duration::from_millis(30);
+
+        assert_eq!(
+            Some(Duration::from_millis(2000 + 750)),
+            params.time_budget(Color::White, overhead)
+        );
+        assert!(params.time_budget(Color::Black, overhead).unwrap() < Duration::from_millis(1000));
+
+        let params = GoParams::parse(&["movetime", "500"]);
+        assert_eq!(
+            Some(Duration::from_millis(470)),
+            params.time_budget(Color::White, overhead)
+        );
+        assert_eq!(
+            None,
+            GoParams::parse(&["infinite"]).time_budget(Color::White, overhead)
+        );
+        assert_eq!(
+            None,
+            GoParams::parse(&["depth", "5"]).time_budget(Color::White, overhead)
+        );
+    }
+
+    #[test]
+    fn test_time_budget_without_time_left() {
+        let overhead = Duration::from_millis(30);
+
+        // a side that is over its time gets a negative clock
+        let params = GoParams::parse(&["wtime", "-100", "btime", "-100", "winc", "1000"]);
+        assert_eq!(Some(Duration::ZERO), params.wtime);
+        assert_eq!(
+            Some(Duration::ZERO),
+            params.time_budget(Color::White, overhead)
+        );
+
+        let params = GoParams::parse(&["wtime", "0", "btime", "1000"]);
+        assert_eq!(
+            Some(Duration::ZERO),
+            params.time_budget(Color::White, overhead)
+        );
+        assert_eq!(
+            Some(Duration::ZERO),
+            GoParams::parse(&["btime", "1000"]).time_budget(Color::White, overhead)
+        );
+    }
+}
@@ -1,5 +1,4 @@
-#![feature(step_trait)]
-#![feature(test)]
+#![cfg_attr(test, feature(step_trait))]
 
 pub mod chess;
 
@@ -1,15 +1,6 @@
-use libchess::chess::board::piece::Color;
-use libchess::chess::board::setup::default_setup;
-use libchess::chess::board::Board;
-use libchess::chess::perft::perft;
-use std::time::Instant;
+use libchess::chess::uci;
+use std::io;
 
 fn main() {
-    let mut b = Board::new();
-    b.populate(default_setup);
-
-    let start = Instant::now();
-    perft(5, &mut b, Color::White);
-    let end = Instant::now();
-    println!("Time elapsed: {:?}", end - start);
+    uci::run(io::stdin().lock(), io::stdout());
 }